@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE forecasts DROP COLUMN suspended;
+
+ALTER TABLE chats DROP COLUMN active;
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+-- Chats that blocked the bot are inactive and their forecasts suspended
+ALTER TABLE chats ADD COLUMN active BOOLEAN DEFAULT TRUE NOT NULL;
+
+ALTER TABLE forecasts ADD COLUMN suspended BOOLEAN DEFAULT FALSE NOT NULL;
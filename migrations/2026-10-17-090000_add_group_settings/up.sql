@@ -0,0 +1,4 @@
+-- Your SQL goes here
+
+-- Marks that the running command of a group member edits the group settings
+ALTER TABLE chats ADD COLUMN editing_group BOOLEAN DEFAULT FALSE NOT NULL;
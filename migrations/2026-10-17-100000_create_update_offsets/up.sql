@@ -0,0 +1,8 @@
+-- Your SQL goes here
+
+-- Last update_id enqueued, long polling resumes from the next one
+CREATE TABLE update_offsets (
+  id BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (id),
+  update_id BIGINT NOT NULL,
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+
+-- Updates already handled, a retried or duplicated ProcessUpdateTask skips them
+CREATE TABLE processed_updates (
+  update_id BIGINT PRIMARY KEY,
+  processed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX processed_updates_processed_at_index ON processed_updates(processed_at);
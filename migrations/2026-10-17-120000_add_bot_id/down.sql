@@ -0,0 +1,21 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE processed_updates DROP CONSTRAINT processed_updates_pkey;
+ALTER TABLE processed_updates DROP COLUMN bot_id;
+ALTER TABLE processed_updates ADD PRIMARY KEY (update_id);
+
+ALTER TABLE update_offsets DROP CONSTRAINT update_offsets_pkey;
+ALTER TABLE update_offsets DROP COLUMN bot_id;
+ALTER TABLE update_offsets ADD COLUMN id BOOLEAN DEFAULT TRUE CHECK (id);
+ALTER TABLE update_offsets ADD PRIMARY KEY (id);
+
+ALTER TABLE forecasts DROP CONSTRAINT fk_chat;
+ALTER TABLE forecasts DROP CONSTRAINT forecasts_bot_id_chat_id_user_id_city_id_key;
+ALTER TABLE chats DROP CONSTRAINT chats_pkey;
+
+ALTER TABLE forecasts DROP COLUMN bot_id;
+ALTER TABLE chats DROP COLUMN bot_id;
+
+ALTER TABLE chats ADD PRIMARY KEY (id, user_id);
+ALTER TABLE forecasts ADD UNIQUE (chat_id, user_id, city_id);
+ALTER TABLE forecasts ADD CONSTRAINT fk_chat FOREIGN KEY (chat_id, user_id) REFERENCES chats(id, user_id);
@@ -0,0 +1,27 @@
+-- Your SQL goes here
+
+-- Every bot keeps its own chats, forecasts and updates. The rows created before
+-- are adopted by the first configured bot when it starts.
+ALTER TABLE forecasts DROP CONSTRAINT fk_chat;
+ALTER TABLE forecasts DROP CONSTRAINT forecasts_chat_id_user_id_city_id_key;
+ALTER TABLE chats DROP CONSTRAINT chats_pkey;
+
+ALTER TABLE chats ADD COLUMN bot_id BIGINT DEFAULT 0 NOT NULL;
+ALTER TABLE forecasts ADD COLUMN bot_id BIGINT DEFAULT 0 NOT NULL;
+ALTER TABLE chats ALTER COLUMN bot_id DROP DEFAULT;
+ALTER TABLE forecasts ALTER COLUMN bot_id DROP DEFAULT;
+
+ALTER TABLE chats ADD PRIMARY KEY (bot_id, id, user_id);
+ALTER TABLE forecasts ADD CONSTRAINT forecasts_bot_id_chat_id_user_id_city_id_key UNIQUE (bot_id, chat_id, user_id, city_id);
+ALTER TABLE forecasts ADD CONSTRAINT fk_chat FOREIGN KEY (bot_id, chat_id, user_id) REFERENCES chats(bot_id, id, user_id) ON UPDATE CASCADE;
+
+ALTER TABLE update_offsets DROP CONSTRAINT update_offsets_pkey;
+ALTER TABLE update_offsets DROP COLUMN id;
+ALTER TABLE update_offsets ADD COLUMN bot_id BIGINT DEFAULT 0 NOT NULL;
+ALTER TABLE update_offsets ALTER COLUMN bot_id DROP DEFAULT;
+ALTER TABLE update_offsets ADD PRIMARY KEY (bot_id);
+
+ALTER TABLE processed_updates DROP CONSTRAINT processed_updates_pkey;
+ALTER TABLE processed_updates ADD COLUMN bot_id BIGINT DEFAULT 0 NOT NULL;
+ALTER TABLE processed_updates ALTER COLUMN bot_id DROP DEFAULT;
+ALTER TABLE processed_updates ADD PRIMARY KEY (bot_id, update_id);
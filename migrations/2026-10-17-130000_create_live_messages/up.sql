@@ -0,0 +1,15 @@
+-- Your SQL goes here
+
+-- Pinned weather messages the bot edits in place, one per chat
+CREATE TABLE live_messages (
+  bot_id BIGINT NOT NULL,
+  chat_id BIGINT NOT NULL,
+  message_id INT NOT NULL,
+  city_id INT NOT NULL REFERENCES cities(id),
+  next_refresh_at TIMESTAMP WITH TIME ZONE NOT NULL,
+  updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+  PRIMARY KEY (bot_id, chat_id)
+);
+
+CREATE INDEX live_messages_next_refresh_at_index ON live_messages(next_refresh_at);
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE forecasts DROP COLUMN paused_until;
+
+ALTER TABLE forecasts DROP COLUMN paused;
@@ -0,0 +1,6 @@
+-- Your SQL goes here
+
+-- Forecasts paused by their user, until paused_until when it is set
+ALTER TABLE forecasts ADD COLUMN paused BOOLEAN DEFAULT FALSE NOT NULL;
+
+ALTER TABLE forecasts ADD COLUMN paused_until TIMESTAMP WITH TIME ZONE;
@@ -0,0 +1,12 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE favorites;
+
+UPDATE chats SET state = 'initial' WHERE state IN ('add_favorite', 'add_favorite_number', 'rename_favorite');
+
+ALTER TABLE chats ALTER COLUMN state DROP DEFAULT;
+ALTER TYPE client_state RENAME TO client_state_old;
+CREATE TYPE client_state AS ENUM ('initial', 'set_city', 'find_city' , 'find_city_number' , 'set_city_number', 'schedule_city', 'schedule_city_number',  'time', 'offset');
+ALTER TABLE chats ALTER COLUMN state TYPE client_state USING state::text::client_state;
+ALTER TABLE chats ALTER COLUMN state SET DEFAULT 'initial';
+DROP TYPE client_state_old;
@@ -0,0 +1,18 @@
+-- Your SQL goes here
+
+-- Cities a chat keeps at hand besides its default city, with an optional name like Home
+CREATE TABLE favorites (
+  id SERIAL PRIMARY KEY,
+  bot_id BIGINT NOT NULL,
+  chat_id BIGINT NOT NULL,
+  user_id BYTEA NOT NULL,
+  city_id INT NOT NULL REFERENCES cities(id),
+  name VARCHAR(80),
+  created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+  UNIQUE (bot_id, chat_id, user_id, city_id),
+  CONSTRAINT fk_chat FOREIGN KEY (bot_id, chat_id, user_id) REFERENCES chats(bot_id, id, user_id) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+ALTER TYPE client_state ADD VALUE 'add_favorite';
+ALTER TYPE client_state ADD VALUE 'add_favorite_number';
+ALTER TYPE client_state ADD VALUE 'rename_favorite';
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE live_messages DROP COLUMN language;
+
+ALTER TABLE chats DROP COLUMN language;
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+
+-- Code of the language the bot speaks in the chat, NULL until it is known
+ALTER TABLE chats ADD COLUMN language VARCHAR(8);
+
+-- A live message keeps the language of who started it
+ALTER TABLE live_messages ADD COLUMN language VARCHAR(8) DEFAULT 'en' NOT NULL;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE live_messages DROP COLUMN units;
+
+ALTER TABLE chats DROP COLUMN units;
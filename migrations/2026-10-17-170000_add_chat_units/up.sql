@@ -0,0 +1,7 @@
+-- Your SQL goes here
+
+-- Unit system of the weather in the chat, NULL for the default metric system
+ALTER TABLE chats ADD COLUMN units VARCHAR(8);
+
+-- A live message keeps the units of who started it
+ALTER TABLE live_messages ADD COLUMN units VARCHAR(8) DEFAULT 'metric' NOT NULL;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+
+-- Instance of the bot running the task, its interrupted tasks are requeued on its next start
+ALTER TABLE fang_tasks ADD COLUMN worker_id VARCHAR;
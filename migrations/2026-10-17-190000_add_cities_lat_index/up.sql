@@ -0,0 +1,4 @@
+-- Your SQL goes here
+
+-- The nearest city to a location is searched in a box around it first
+CREATE INDEX cities_lat_idx ON cities (lat);
@@ -1,3 +1,7 @@
+pub mod comparison;
+pub mod delivery_schedule;
+pub mod inline_query_processor;
 pub mod process_update_task;
+pub mod registry;
 
 pub use process_update_task::ProcessUpdateTask;
@@ -0,0 +1,231 @@
+use crate::locale::Language;
+use crate::open_weather_map::units::Units;
+use crate::open_weather_map::weather::WeatherForecast;
+use std::fmt::Write;
+
+// Forecast entries are 3 hours apart, 3 of them cover the next 9 hours.
+const RAIN_FORECAST_ENTRIES: usize = 3;
+
+/// Weather of one of the compared cities.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CityComparison {
+    pub label: String,
+    pub temp: f64,
+    pub humidity: u32,
+    pub rain_probability: u32,
+    pub description: String,
+}
+
+impl CityComparison {
+    /// Temperature, humidity and description of the first forecast entry,
+    /// the closest to now. The rain probability is the highest of the next hours.
+    /// None when the forecast has no entries.
+    pub fn new(label: String, forecast: &WeatherForecast) -> Option<Self> {
+        let current = forecast.list.first()?;
+
+        let rain_probability = forecast
+            .list
+            .iter()
+            .take(RAIN_FORECAST_ENTRIES)
+            .map(|entry| entry.pop)
+            .fold(0.0, f32::max);
+
+        let description = current
+            .weather
+            .first()
+            .map(|info| info.description.clone())
+            .unwrap_or_default();
+
+        Some(Self {
+            label,
+            temp: current.main.temp,
+            humidity: current.main.humidity,
+            rain_probability: (rain_probability * 100.0).round() as u32,
+            description,
+        })
+    }
+}
+
+/// Splits `Madrid, Lisbon, Paris, FR` or `Madrid vs Lisbon` into the city queries.
+/// A two letter part after a comma is the country of the previous city.
+pub fn split_cities(arguments: &str) -> Vec<String> {
+    let mut cities: Vec<String> = Vec::new();
+
+    let parts = arguments
+        .split([',', ';', '\n'])
+        .flat_map(|part| part.split(" vs "))
+        .map(str::trim)
+        .filter(|part| !part.is_empty());
+
+    for part in parts {
+        let is_country = part.len() == 2 && part.chars().all(|c| c.is_ascii_alphabetic());
+
+        match cities.last_mut() {
+            Some(city) if is_country && !city.contains(',') => {
+                city.push_str(", ");
+                city.push_str(part);
+            }
+            _ => cities.push(part.to_string()),
+        }
+    }
+
+    cities
+}
+
+/// Table with a column per value, in a `<pre>` block so the columns stay aligned,
+/// followed by the warmest, the driest and the rainiest city.
+pub fn comparison_table(
+    cities: &[CityComparison],
+    language: Language,
+    units: Units,
+) -> Result<String, std::fmt::Error> {
+    let header = [
+        language.text("compare_city"),
+        language.text("compare_temp"),
+        language.text("compare_humidity"),
+        language.text("compare_rain"),
+    ];
+
+    let label_width = cities
+        .iter()
+        .map(|city| city.label.chars().count())
+        .max()
+        .unwrap_or_default()
+        .max(header[0].chars().count());
+    let temp_width = header[1].chars().count().max(7);
+    let humidity_width = header[2].chars().count().max(4);
+    let rain_width = header[3].chars().count().max(4);
+
+    let mut table = String::new();
+
+    writeln!(
+        &mut table,
+        "{:<label_width$}  {:>temp_width$}  {:>humidity_width$}  {:>rain_width$}",
+        header[0], header[1], header[2], header[3],
+    )?;
+
+    for city in cities {
+        writeln!(
+            &mut table,
+            "{:<label_width$}  {:>temp_width$}  {:>humidity_width$}  {:>rain_width$}",
+            city.label,
+            format!("{:.1}{}", city.temp, units.temperature_symbol()),
+            format!("{}%", city.humidity),
+            format!("{}%", city.rain_probability),
+        )?;
+    }
+
+    let mut text = format!("<pre>{}</pre>\n", escape(table.trim_end()));
+
+    let warmest = cities.iter().max_by(|a, b| a.temp.total_cmp(&b.temp));
+    let driest = cities.iter().min_by_key(|city| city.humidity);
+    let rainiest = cities.iter().max_by_key(|city| city.rain_probability);
+
+    if let (Some(warmest), Some(driest), Some(rainiest)) = (warmest, driest, rainiest) {
+        let warmest_temp = format!("{:.1} {}", warmest.temp, units.temperature_symbol());
+
+        writeln!(
+            &mut text,
+            "{}",
+            language.format(
+                "compare_warmest",
+                &[&escape(&warmest.label), &warmest_temp, &warmest.description]
+            )
+        )?;
+        writeln!(
+            &mut text,
+            "{}",
+            language.format(
+                "compare_driest",
+                &[&escape(&driest.label), &driest.humidity]
+            )
+        )?;
+        write!(
+            &mut text,
+            "{}",
+            language.format(
+                "compare_rainiest",
+                &[&escape(&rainiest.label), &rainiest.rain_probability]
+            )
+        )?;
+    }
+
+    Ok(text)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod comparison_test {
+    use super::*;
+
+    fn city(label: &str, temp: f64, humidity: u32, rain_probability: u32) -> CityComparison {
+        CityComparison {
+            label: label.to_string(),
+            temp,
+            humidity,
+            rain_probability,
+            description: "clear sky".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_empty_forecast_is_not_compared() {
+        let forecast = WeatherForecast {
+            cod: "200".to_string(),
+            list: Vec::new(),
+        };
+
+        assert_eq!(
+            CityComparison::new("Madrid,ES".to_string(), &forecast),
+            None
+        );
+    }
+
+    #[test]
+    fn test_split_cities() {
+        assert_eq!(
+            split_cities("Madrid, Lisbon, Paris, FR"),
+            vec!["Madrid", "Lisbon", "Paris, FR"]
+        );
+        assert_eq!(
+            split_cities("Madrid vs Lisbon vs Paris"),
+            vec!["Madrid", "Lisbon", "Paris"]
+        );
+        assert_eq!(
+            split_cities("Paris, US; Paris, FR"),
+            vec!["Paris, US", "Paris, FR"]
+        );
+    }
+
+    #[test]
+    fn test_comparison_table() {
+        let cities = vec![
+            city("Madrid,ES", 24.3, 30, 0),
+            city("Lisbon,PT", 19.0, 65, 40),
+            city("Paris,FR", 8.5, 80, 90),
+        ];
+
+        let text = comparison_table(&cities, Language::English, Units::Metric).unwrap();
+
+        assert_eq!(
+            text,
+            "<pre>City          Temp  Humidity  Rain 9h\n\
+             Madrid,ES   24.3ºC       30%       0%\n\
+             Lisbon,PT   19.0ºC       65%      40%\n\
+             Paris,FR     8.5ºC       80%      90%</pre>\n\
+             🔥 Warmest: Madrid,ES 24.3 ºC, clear sky\n\
+             🏜️ Driest: Madrid,ES 30 % humidity\n\
+             ☔ Highest rain probability: Paris,FR 90 %"
+        );
+
+        let text = comparison_table(&cities, Language::English, Units::Imperial).unwrap();
+
+        assert!(text.contains("Madrid,ES   24.3ºF"));
+        assert!(text.contains("🔥 Warmest: Madrid,ES 24.3 ºF, clear sky"));
+    }
+}
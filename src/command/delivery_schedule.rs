@@ -0,0 +1,303 @@
+use crate::locale::Language;
+use std::fmt;
+
+const DAY_NAMES: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+
+const DAY_KEYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+const EVERY_DAY: u8 = 0b111_1111;
+const WEEKDAYS: u8 = 0b001_1111;
+const WEEKENDS: u8 = 0b110_0000;
+
+/// Days of the week a forecast is delivered, bit 0 is Monday.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct Weekdays(u8);
+
+/// Local time and days of a scheduled forecast, like `weekdays at 07:30`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct DeliverySchedule {
+    pub hour: i8,
+    pub minutes: i8,
+    pub days: Weekdays,
+}
+
+impl Weekdays {
+    /// Parses `daily`, `weekdays`, `weekends`, `every Monday`, `Sat,Sun` or `Mon-Fri`.
+    /// An empty text is every day.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim().to_lowercase();
+        let text = text.strip_prefix("every").unwrap_or(&text).trim();
+        let text = text.strip_prefix("on ").unwrap_or(text).trim();
+
+        match text {
+            "" | "day" | "daily" => return Some(Self(EVERY_DAY)),
+            "weekday" | "weekdays" => return Some(Self(WEEKDAYS)),
+            "weekend" | "weekends" => return Some(Self(WEEKENDS)),
+            _ => {}
+        }
+
+        let mut days = 0;
+
+        for item in text.split(',').map(str::trim) {
+            days |= match item.split_once('-') {
+                Some((first, last)) => Self::range(Self::day(first)?, Self::day(last)?),
+                None => 1 << Self::day(item)?,
+            };
+        }
+
+        Some(Self(days))
+    }
+
+    fn day(name: &str) -> Option<usize> {
+        let name = name.trim();
+
+        if name.len() < 3 {
+            return None;
+        }
+
+        let name = name.strip_suffix('s').unwrap_or(name);
+
+        DAY_NAMES.iter().position(|day| {
+            let day = day.to_lowercase();
+
+            day == name || day[..3] == *name
+        })
+    }
+
+    fn range(first: usize, last: usize) -> u8 {
+        let mut days = 0;
+        let mut day = first;
+
+        loop {
+            days |= 1 << day;
+
+            if day == last {
+                return days;
+            }
+
+            day = (day + 1) % 7;
+        }
+    }
+
+    /// Moves every day `days` days forward, or backward when negative.
+    pub fn shift(&self, days: i8) -> Self {
+        let days = days.rem_euclid(7) as u32;
+
+        Self(((self.0 << days) | (self.0 >> (7 - days))) & EVERY_DAY)
+    }
+
+    /// Day of week field of a cron expression, `*` or day names like `Mon,Wed`.
+    pub fn cron_field(&self) -> String {
+        if self.0 == EVERY_DAY {
+            return "*".to_string();
+        }
+
+        self.days()
+            .map(|day| &DAY_NAMES[day][..3])
+            .collect::<Vec<&str>>()
+            .join(",")
+    }
+
+    pub fn from_cron_field(field: &str) -> Option<Self> {
+        match field {
+            "*" => Some(Self(EVERY_DAY)),
+            _ => Self::parse(field),
+        }
+    }
+
+    fn days(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..7).filter(move |day| self.0 & (1 << day) != 0)
+    }
+
+    pub fn describe(&self, language: Language) -> String {
+        let names: Vec<&str> = self
+            .days()
+            .map(|day| language.text(DAY_KEYS[day]))
+            .collect();
+
+        match (self.0, names.as_slice()) {
+            (EVERY_DAY, _) => language.text("every_day").to_string(),
+            (WEEKDAYS, _) => language.text("on_weekdays").to_string(),
+            (WEEKENDS, _) => language.text("on_weekends").to_string(),
+            (_, [day]) => language.format("every_weekday", &[day]),
+            (_, [days @ .., last]) => language.format("on_days", &[&days.join(", "), last]),
+            (_, []) => language.text("no_days").to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Weekdays {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.describe(Language::English))
+    }
+}
+
+impl DeliverySchedule {
+    /// Parses a time in format `hour:minutes` with the optional days before or
+    /// after it, like `07:30`, `07:30 weekdays` or `Sat,Sun at 09:00`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let words: Vec<&str> = text.split_whitespace().collect();
+
+        let position = words
+            .iter()
+            .position(|word| Self::parse_time(word).is_some())?;
+        let (hour, minutes) = Self::parse_time(words[position])?;
+
+        let days: Vec<&str> = words
+            .iter()
+            .enumerate()
+            .filter(|(index, word)| *index != position && !word.eq_ignore_ascii_case("at"))
+            .map(|(_, word)| *word)
+            .collect();
+
+        let days = Weekdays::parse(&days.join(" "))?;
+
+        Some(Self {
+            hour,
+            minutes,
+            days,
+        })
+    }
+
+    pub fn parse_time(text: &str) -> Option<(i8, i8)> {
+        let (hour, minutes) = text.split_once(':')?;
+
+        let hour = hour
+            .parse::<i8>()
+            .ok()
+            .filter(|hour| (0..=23).contains(hour))?;
+        let minutes = minutes
+            .parse::<i8>()
+            .ok()
+            .filter(|minutes| (0..=59).contains(minutes))?;
+
+        Some((hour, minutes))
+    }
+
+    /// Cron expression in UTC. The days move with the hour when the offset
+    /// crosses midnight.
+    pub fn cron_expression(&self, offset: i8) -> String {
+        let hour_utc = self.hour - offset;
+        let days_utc = self.days.shift(hour_utc.div_euclid(24));
+
+        format!(
+            "0 {} {} * * {} *",
+            self.minutes,
+            hour_utc.rem_euclid(24),
+            days_utc.cron_field()
+        )
+    }
+
+    pub fn from_cron_expression(cron_expression: &str, offset: i8) -> Option<Self> {
+        let fields: Vec<&str> = cron_expression.split_whitespace().collect();
+
+        if fields.len() != 7 {
+            return None;
+        }
+
+        let minutes = fields[1].parse::<i8>().ok()?;
+        let hour_utc = fields[2].parse::<i8>().ok()?;
+        let days_utc = Weekdays::from_cron_field(fields[5])?;
+
+        let hour = hour_utc + offset;
+
+        Some(Self {
+            hour: hour.rem_euclid(24),
+            minutes,
+            days: days_utc.shift(hour.div_euclid(24)),
+        })
+    }
+
+    pub fn describe(&self, language: Language) -> String {
+        let time = format!("{:02}:{:02}", self.hour, self.minutes);
+
+        language.format("schedule_at", &[&self.days.describe(language), &time])
+    }
+}
+
+impl fmt::Display for DeliverySchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.describe(Language::English))
+    }
+}
+
+#[cfg(test)]
+mod delivery_schedule_test {
+    use super::*;
+    use crate::db::Repo;
+
+    #[test]
+    fn test_parse_weekdays() {
+        assert_eq!(Weekdays::parse(""), Some(Weekdays(EVERY_DAY)));
+        assert_eq!(Weekdays::parse("weekdays"), Some(Weekdays(WEEKDAYS)));
+        assert_eq!(Weekdays::parse("Sat,Sun"), Some(Weekdays(WEEKENDS)));
+        assert_eq!(Weekdays::parse("Mon-Fri"), Some(Weekdays(WEEKDAYS)));
+        assert_eq!(Weekdays::parse("every Monday"), Some(Weekdays(1)));
+        assert_eq!(Weekdays::parse("on Mondays"), Some(Weekdays(1)));
+        assert_eq!(Weekdays::parse("Sun-Mon"), Some(Weekdays(0b100_0001)));
+        assert_eq!(Weekdays::parse("someday"), None);
+    }
+
+    #[test]
+    fn test_describe_schedule() {
+        let schedule = DeliverySchedule::parse("weekdays at 07:30").unwrap();
+        assert_eq!(schedule.to_string(), "on weekdays at 07:30");
+
+        let schedule = DeliverySchedule::parse("9:00 Mon,Wed,Fri").unwrap();
+        assert_eq!(
+            schedule.to_string(),
+            "on Monday, Wednesday and Friday at 09:00"
+        );
+
+        let schedule = DeliverySchedule::parse("every Monday 18:15").unwrap();
+        assert_eq!(schedule.to_string(), "every Monday at 18:15");
+
+        let schedule = DeliverySchedule::parse("Sat,Sun,Mon 08:00").unwrap();
+        assert_eq!(
+            schedule.describe(Language::Spanish),
+            "los lunes, sábado y domingo a las 08:00"
+        );
+
+        assert_eq!(DeliverySchedule::parse("24:00"), None);
+        assert_eq!(DeliverySchedule::parse("07:30 someday"), None);
+    }
+
+    #[test]
+    fn test_cron_expression() {
+        let daily = DeliverySchedule::parse("07:30").unwrap();
+        assert_eq!(daily.cron_expression(2), "0 30 5 * * * *");
+
+        // 01:00 on Monday in UTC+2 is 23:00 on Sunday in UTC.
+        let monday = DeliverySchedule::parse("01:00 Mon").unwrap();
+        assert_eq!(monday.cron_expression(2), "0 0 23 * * Sun *");
+
+        let weekends = DeliverySchedule::parse("Sat,Sun at 22:00").unwrap();
+        assert_eq!(weekends.cron_expression(-3), "0 0 1 * * Mon,Sun *");
+
+        for (schedule, offset) in [(daily, 2), (monday, 2), (weekends, -3)] {
+            let cron_expression = schedule.cron_expression(offset);
+
+            assert!(Repo::calculate_next_delivery(&cron_expression).is_ok());
+            assert_eq!(
+                DeliverySchedule::from_cron_expression(&cron_expression, offset),
+                Some(schedule)
+            );
+        }
+    }
+}
@@ -0,0 +1,143 @@
+use crate::db::Repo;
+use crate::locale::Language;
+use crate::open_weather_map::client::WeatherApiClient;
+use crate::open_weather_map::units::Units;
+use crate::open_weather_map::weather::Weather;
+use crate::open_weather_map::City;
+use crate::telegram::client::ApiClient;
+use crate::BotError;
+use frankenstein::InlineQuery;
+use frankenstein::InlineQueryResult;
+use frankenstein::InlineQueryResultArticle;
+use frankenstein::InputMessageContent;
+use frankenstein::InputTextMessageContent;
+use frankenstein::ParseMode;
+use typed_builder::TypedBuilder;
+
+const MIN_QUERY_LENGTH: usize = 3;
+const MAX_RESULTS: usize = 5;
+// Seconds Telegram keeps the answer of the same query of a user cached on its side,
+// repeated keystrokes are answered by Telegram and the weather comes from the cache
+// of the client, so the queries are answered right away.
+const CACHE_TIME: u32 = 300;
+
+#[derive(TypedBuilder)]
+pub struct InlineQueryProcessor {
+    api: &'static ApiClient,
+    repo: &'static Repo,
+    inline_query: InlineQuery,
+}
+
+impl InlineQueryProcessor {
+    pub async fn create(
+        api: &'static ApiClient,
+        inline_query: InlineQuery,
+    ) -> Result<Self, BotError> {
+        let repo = Repo::repo().await?;
+
+        let processor = Self::builder()
+            .repo(repo)
+            .api(api)
+            .inline_query(inline_query)
+            .build();
+
+        Ok(processor)
+    }
+
+    pub async fn process(&self) -> Result<(), BotError> {
+        let query = self.inline_query.query.trim();
+
+        if query.chars().count() < MIN_QUERY_LENGTH {
+            return self.answer(vec![]).await;
+        }
+
+        let rows = self.repo.get_city_by_pattern(query).await?;
+
+        let weather_client = WeatherApiClient::weather_client().await;
+
+        // Inline queries do not come from a chat, the user language of Telegram is used.
+        let language = self
+            .inline_query
+            .from
+            .language_code
+            .as_deref()
+            .and_then(Language::from_code)
+            .unwrap_or_default();
+        let units = self.private_chat_units().await;
+
+        let mut results = Vec::new();
+
+        for row in rows.iter().take(MAX_RESULTS) {
+            let city = Repo::record_to_city(row);
+
+            match weather_client.fetch_cached(&city, language, units).await {
+                Ok(weather) => results.push(Self::article(&city, &weather, language)),
+                Err(err) => log::error!("Failed to fetch weather of {} {:?}", city, err),
+            }
+        }
+
+        self.answer(results).await
+    }
+
+    // The units chosen with /units in the private chat with the bot, if any.
+    async fn private_chat_units(&self) -> Units {
+        let user_id = self.inline_query.from.id;
+
+        match self
+            .repo
+            .get_chat(self.api.bot_id(), &(user_id as i64), user_id)
+            .await
+        {
+            Ok(chat) => chat
+                .units
+                .as_deref()
+                .and_then(Units::from_code)
+                .unwrap_or_default(),
+            Err(_) => Units::default(),
+        }
+    }
+
+    fn article(city: &City, weather: &Weather, language: Language) -> InlineQueryResult {
+        let description = match weather.weather.first() {
+            Some(info) => format!(
+                "{}, {}",
+                weather.units.temperature(weather.main.temp),
+                info.description
+            ),
+            None => weather.units.temperature(weather.main.temp),
+        };
+
+        let text = language.format(
+            "city_weather",
+            &[
+                &city.name,
+                &city.country,
+                &city.coord.lat,
+                &city.coord.lon,
+                weather,
+            ],
+        );
+
+        let content = InputTextMessageContent::builder()
+            .message_text(text)
+            .parse_mode(ParseMode::Html)
+            .build();
+
+        let article = InlineQueryResultArticle::builder()
+            .id(city.id.to_string())
+            .title(city.short_name())
+            .description(description)
+            .input_message_content(InputMessageContent::Text(content))
+            .build();
+
+        InlineQueryResult::Article(article)
+    }
+
+    async fn answer(&self, results: Vec<InlineQueryResult>) -> Result<(), BotError> {
+        self.api
+            .answer_inline_query(self.inline_query.id.clone(), results, CACHE_TIME)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -1,32 +1,91 @@
+use super::comparison;
+use super::comparison::CityComparison;
+use super::delivery_schedule::DeliverySchedule;
+use super::inline_query_processor::InlineQueryProcessor;
+use super::registry;
 use crate::db::BotDbError;
 use crate::db::Chat;
 use crate::db::ClientState;
+use crate::db::Favorite;
 use crate::db::Forecast;
 use crate::db::Repo;
+use crate::db::SHARED_USER_ID;
+use crate::deliver;
 use crate::deliver::ScheduleWeatherTask;
+use crate::locale::Language;
 use crate::open_weather_map::client::WeatherApiClient;
+use crate::open_weather_map::units::Units;
 use crate::open_weather_map::City;
+use crate::open_weather_map::Coord;
+use crate::shutdown;
 use crate::telegram::client::ApiClient;
 use crate::BotError;
+use chrono::Duration;
+use chrono::NaiveDate;
+use chrono::TimeZone;
 use fang::async_trait;
 use fang::asynk::async_queue::AsyncQueueable;
 use fang::serde::Deserialize;
 use fang::serde::Serialize;
 use fang::typetag;
 use fang::AsyncRunnable;
+use fang::DateTime;
 use fang::FangError;
+use fang::Scheduled;
+use fang::Utc;
+use frankenstein::CallbackQuery;
+use frankenstein::ChatMember;
+use frankenstein::ChatType;
+use frankenstein::InlineKeyboardButton;
+use frankenstein::InlineKeyboardMarkup;
+use frankenstein::InlineQuery;
+use frankenstein::Message;
 use frankenstein::Update;
 use frankenstein::UpdateContent;
-use std::fmt::Write;
-use std::str::FromStr;
+use frankenstein::User;
+use std::fmt;
+use tokio::task;
 use typed_builder::TypedBuilder;
 
-const BOT_NAME: &str = "@RustWeather77Bot";
 pub const TASK_TYPE: &str = "process_update";
+// Updates of a chat always go to the same partition, which is processed by a
+// single worker, so they run in arrival order while other chats run in parallel.
+// A failed update holds back the later ones of its chat until its retry.
+pub const CHAT_PARTITIONS: u32 = 5;
+// A failed update is retried a few times, 5, 10 and 15 seconds later.
+const UPDATE_RETRIES: i32 = 3;
+const UPDATE_RETRY_SECONDS: u32 = 5;
+// Telegram keeps undelivered updates for 24 hours, older records can not be duplicated.
+const PROCESSED_UPDATES_RETENTION_DAYS: i64 = 7;
+// Every day at 03:00 UTC the CleanProcessedUpdates task is executed.
+pub const CRON_CLEAN_PROCESSED_UPDATES: &str = "0 0 3 * * * *";
+const CITY_CALLBACK_PREFIX: &str = "city:";
+const EDIT_CALLBACK_PREFIX: &str = "edit:";
+const REMOVE_CALLBACK_PREFIX: &str = "remove:";
+const UNFAVORITE_CALLBACK_PREFIX: &str = "unfavorite:";
+const RENAME_CALLBACK_PREFIX: &str = "rename:";
+const LANGUAGE_CALLBACK_PREFIX: &str = "language:";
+const GROUP_LANGUAGE_CALLBACK_PREFIX: &str = "group_language:";
+const UNITS_CALLBACK_PREFIX: &str = "units:";
+const GROUP_UNITS_CALLBACK_PREFIX: &str = "group_units:";
+// More matches than this are not offered as buttons, the city has to be written more precisely.
+const MAX_CITY_MATCHES: usize = 30;
+const MAX_FAVORITES: usize = 10;
+const MAX_COMPARED_CITIES: usize = 6;
+const MAX_FAVORITE_NAME_LENGTH: usize = 32;
+// Actions of `t.me/<bot>?start=<action>_<city id>` links.
+const WEATHER_PAYLOAD: &str = "city";
+const DEFAULT_PAYLOAD: &str = "default";
+const SCHEDULE_PAYLOAD: &str = "schedule";
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(crate = "fang::serde")]
 pub struct ProcessUpdateTask {
+    #[serde(default)]
+    bot_id: i64,
+    // Stored so the queue can hold back the later updates of the chat.
+    #[serde(default)]
+    chat_id: Option<i64>,
     update: Update,
 }
 
@@ -41,10 +100,47 @@ pub enum Command {
     CurrentDefaultCity,
     CurrentOffset,
     UnSchedule,
+    Schedules,
+    Pause,
+    Resume,
+    Favorites,
+    AddFavorite,
+    RemoveFavorite,
+    RenameFavorite,
+    Compare,
+    Language,
+    Units,
     SetOffset,
+    GroupDefault,
+    SetGroupCity,
+    SetGroupOffset,
+    SetGroupLanguage,
+    SetGroupUnits,
+    ScheduleGroup,
+    UnScheduleGroup,
+    Live,
+    StopLive,
+    StartWith(StartPayload),
+    SelectCity(i32),
+    EditForecast(i32),
+    RemoveForecast(i32),
+    Unfavorite(i32),
+    NameFavorite(i32),
+    SetLanguage(Language),
+    ChooseGroupLanguage(Language),
+    SetUnits(Units),
+    ChooseGroupUnits(Units),
+    Location,
     UnknownCommand(String),
 }
 
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum StartPayload {
+    Weather(i32),
+    SetDefault(i32),
+    Schedule(i32),
+}
+
 #[derive(TypedBuilder)]
 pub struct UpdateProcessor {
     api: &'static ApiClient,
@@ -54,213 +150,1826 @@ pub struct UpdateProcessor {
     username: String,
     command: Command,
     chat: Chat,
+    language: Language,
+    units: Units,
+    // Shared settings of the group the update comes from.
+    #[builder(default)]
+    group: Option<Chat>,
+    // Everybody in a channel sees its keyboards, only the admins may press them.
+    #[builder(default)]
+    channel_member_id: Option<u64>,
+    #[builder(default)]
+    callback_query_id: Option<String>,
+    #[builder(default)]
+    location: Option<Coord>,
+    // Text after the command, like `Madrid` in `/find_city Madrid`.
+    #[builder(default)]
+    arguments: Option<String>,
+}
+
+impl Command {
+    // In groups a command can be addressed to one bot with `/command@bot_name`.
+    fn parse(text: &str, bot_name: &str) -> Self {
+        let command_str = text.replace(&format!("@{}", bot_name), "");
+        let command_str = command_str.trim();
+
+        // Deep links send `/start <payload>`, an unknown payload just shows the help.
+        if let Some(payload) = command_str.strip_prefix("/start ") {
+            return match StartPayload::parse(payload.trim()) {
+                Some(payload) => Command::StartWith(payload),
+                None => Command::Start,
+            };
+        }
+
+        let name = command_str.split_whitespace().next().unwrap_or_default();
+
+        match name.strip_prefix('/').and_then(registry::find) {
+            Some(definition) => definition.command.clone(),
+            None => Command::UnknownCommand(command_str.to_string()),
+        }
+    }
+
+    // A location asks for the weather there only in the private chat, in groups it
+    // is just shared with the members unless a flow asking for a city waits for it.
+    fn from_message(text: &str, has_location: bool, chat_type: ChatType, bot_name: &str) -> Self {
+        match chat_type {
+            ChatType::Private if has_location => Command::Location,
+            _ => Command::parse(text, bot_name),
+        }
+    }
+
+    fn arguments(text: &str, bot_name: &str) -> Option<String> {
+        let command_str = text.replace(&format!("@{}", bot_name), "");
+
+        command_str
+            .trim()
+            .strip_prefix('/')
+            .and_then(|command| command.split_once(char::is_whitespace))
+            .map(|(_, arguments)| arguments.trim().to_string())
+            .filter(|arguments| !arguments.is_empty())
+    }
+
+    // Inline keyboard buttons carry the city id, so the selected city
+    // does not depend on re-running the pattern search.
+    fn from_callback_data(data: &str) -> Self {
+        let parse_id = |prefix: &str| {
+            data.strip_prefix(prefix)
+                .and_then(|id| id.parse::<i32>().ok())
+        };
+
+        if let Some(city_id) = parse_id(CITY_CALLBACK_PREFIX) {
+            Command::SelectCity(city_id)
+        } else if let Some(forecast_id) = parse_id(EDIT_CALLBACK_PREFIX) {
+            Command::EditForecast(forecast_id)
+        } else if let Some(forecast_id) = parse_id(REMOVE_CALLBACK_PREFIX) {
+            Command::RemoveForecast(forecast_id)
+        } else if let Some(favorite_id) = parse_id(UNFAVORITE_CALLBACK_PREFIX) {
+            Command::Unfavorite(favorite_id)
+        } else if let Some(favorite_id) = parse_id(RENAME_CALLBACK_PREFIX) {
+            Command::NameFavorite(favorite_id)
+        } else if let Some(language) = data
+            .strip_prefix(LANGUAGE_CALLBACK_PREFIX)
+            .and_then(Language::from_code)
+        {
+            Command::SetLanguage(language)
+        } else if let Some(language) = data
+            .strip_prefix(GROUP_LANGUAGE_CALLBACK_PREFIX)
+            .and_then(Language::from_code)
+        {
+            Command::ChooseGroupLanguage(language)
+        } else if let Some(units) = data
+            .strip_prefix(UNITS_CALLBACK_PREFIX)
+            .and_then(Units::from_code)
+        {
+            Command::SetUnits(units)
+        } else if let Some(units) = data
+            .strip_prefix(GROUP_UNITS_CALLBACK_PREFIX)
+            .and_then(Units::from_code)
+        {
+            Command::ChooseGroupUnits(units)
+        } else {
+            Command::UnknownCommand(data.to_string())
+        }
+    }
+}
+
+impl StartPayload {
+    fn parse(payload: &str) -> Option<Self> {
+        let (action, city_id) = payload.split_once('_')?;
+        let city_id = city_id.parse::<i32>().ok()?;
+
+        match action {
+            WEATHER_PAYLOAD => Some(StartPayload::Weather(city_id)),
+            DEFAULT_PAYLOAD => Some(StartPayload::SetDefault(city_id)),
+            SCHEDULE_PAYLOAD => Some(StartPayload::Schedule(city_id)),
+            _ => None,
+        }
+    }
 }
 
-impl FromStr for Command {
-    type Err = ();
+impl UpdateProcessor {
+    pub async fn create(api: &'static ApiClient, update: Update) -> Result<Self, BotError> {
+        match &update.content {
+            UpdateContent::Message(message) | UpdateContent::ChannelPost(message) => {
+                Self::from_message(api, message).await
+            }
+            UpdateContent::CallbackQuery(callback_query) => {
+                Self::from_callback_query(api, callback_query).await
+            }
+            _ => {
+                log::error!("Update is not a message {:?}", update);
+
+                Err(BotError::UpdateNotMessage("no message".to_string()))
+            }
+        }
+    }
+
+    async fn from_message(api: &'static ApiClient, message: &Message) -> Result<Self, BotError> {
+        let location = message.location.as_ref().map(|location| {
+            Coord::builder()
+                .lat(location.latitude)
+                .lon(location.longitude)
+                .build()
+        });
+
+        if message.text.is_none() && location.is_none() {
+            log::error!("Update doesn't contain any text {:?}", message);
+
+            return Err(BotError::UpdateNotMessage("no text".to_string()));
+        }
+
+        let text = message.text.clone().unwrap_or_default();
+
+        let repo = Repo::repo().await?;
+
+        let bot_id = api.bot_id();
+        let chat_id: i64 = message.chat.id;
+
+        // Channel posts are written by its admins on behalf of the channel,
+        // which is configured as a whole.
+        let (user_id, username) = match message.chat.type_field {
+            ChatType::Channel => (SHARED_USER_ID, Self::channel_name(message)),
+            _ => {
+                let user = message.from.clone().expect("User not set");
+
+                (user.id, Self::username(&user))
+            }
+        };
+
+        let mut chat = repo.find_or_create_chat(bot_id, &chat_id, user_id).await?;
+        let group = Self::group_settings(repo, bot_id, chat_id, message.chat.type_field).await?;
+        let language = Self::chat_language(repo, &mut chat, message.from.as_deref()).await?;
+        let units = Self::chat_units(&chat);
+
+        // Writing to the bot again means it was unblocked.
+        if !chat.active {
+            let forecasts = repo.activate_chat(bot_id, &chat_id).await?;
+
+            log::info!(
+                "Chat {} is active again, {} forecasts restored",
+                chat_id,
+                forecasts.len()
+            );
+
+            chat.active = true;
+        }
+
+        let command = Command::from_message(
+            &text,
+            location.is_some(),
+            message.chat.type_field,
+            api.bot_name(),
+        );
+
+        let arguments = Command::arguments(&text, api.bot_name());
+
+        let processor = Self::builder()
+            .repo(repo)
+            .api(api)
+            .message_id(message.message_id)
+            .text(text)
+            .username(username)
+            .chat(chat)
+            .language(language)
+            .units(units)
+            .group(group)
+            .command(command)
+            .location(location)
+            .arguments(arguments)
+            .build();
+
+        Ok(processor)
+    }
+
+    async fn from_callback_query(
+        api: &'static ApiClient,
+        callback_query: &CallbackQuery,
+    ) -> Result<Self, BotError> {
+        let (message, data) = match (&callback_query.message, &callback_query.data) {
+            (Some(message), Some(data)) => (message, data),
+            _ => {
+                log::error!(
+                    "Callback query without message or data {:?}",
+                    callback_query
+                );
+
+                return Err(BotError::UpdateNotMessage("no callback data".to_string()));
+            }
+        };
+
+        let repo = Repo::repo().await?;
+
+        let bot_id = api.bot_id();
+        let chat_id: i64 = message.chat.id;
+        let user = &callback_query.from;
+
+        let (user_id, channel_member_id) = match message.chat.type_field {
+            ChatType::Channel => (SHARED_USER_ID, Some(user.id)),
+            _ => (user.id, None),
+        };
+
+        let mut chat = repo.find_or_create_chat(bot_id, &chat_id, user_id).await?;
+        let group = Self::group_settings(repo, bot_id, chat_id, message.chat.type_field).await?;
+        let language = Self::chat_language(repo, &mut chat, Some(user)).await?;
+        let units = Self::chat_units(&chat);
+        let username = Self::username(user);
+
+        let command = Command::from_callback_data(data);
+
+        let processor = Self::builder()
+            .repo(repo)
+            .api(api)
+            .message_id(message.message_id)
+            .text(data.clone())
+            .username(username)
+            .chat(chat)
+            .language(language)
+            .units(units)
+            .group(group)
+            .command(command)
+            .channel_member_id(channel_member_id)
+            .callback_query_id(Some(callback_query.id.clone()))
+            .build();
+
+        Ok(processor)
+    }
+
+    async fn group_settings(
+        repo: &Repo,
+        bot_id: i64,
+        chat_id: i64,
+        chat_type: ChatType,
+    ) -> Result<Option<Chat>, BotError> {
+        match chat_type {
+            ChatType::Group | ChatType::Supergroup => Ok(Some(
+                repo.find_or_create_chat(bot_id, &chat_id, SHARED_USER_ID)
+                    .await?,
+            )),
+            _ => Ok(None),
+        }
+    }
+
+    // Chat row whose settings the running command changes. Members of a group
+    // edit the group row after an admin-only group command, their own otherwise.
+    fn settings(&self) -> &Chat {
+        match &self.group {
+            Some(group) if self.chat.editing_group => group,
+            _ => &self.chat,
+        }
+    }
+
+    // The language chosen with /language, otherwise the one of the Telegram app.
+    // It is saved, so the scheduled forecasts are delivered in it too.
+    async fn chat_language(
+        repo: &Repo,
+        chat: &mut Chat,
+        user: Option<&User>,
+    ) -> Result<Language, BotError> {
+        if let Some(language) = chat.language.as_deref().and_then(Language::from_code) {
+            return Ok(language);
+        }
+
+        let language = user
+            .and_then(|user| user.language_code.as_deref())
+            .and_then(Language::from_code);
+
+        match language {
+            Some(language) => {
+                repo.modify_language(chat.bot_id, &chat.id, chat.user_id, language.code())
+                    .await?;
+
+                chat.language = Some(language.code().to_string());
+
+                Ok(language)
+            }
+            None => Ok(Language::default()),
+        }
+    }
+
+    fn chat_units(chat: &Chat) -> Units {
+        chat.units
+            .as_deref()
+            .and_then(Units::from_code)
+            .unwrap_or_default()
+    }
+
+    // Texts about the settings of a group or a channel have their own variant.
+    fn owner_text(&self, key: &str, settings: &Chat, args: &[&dyn fmt::Display]) -> String {
+        let owner = match (settings.user_id, &self.group) {
+            (SHARED_USER_ID, Some(_)) => "group",
+            (SHARED_USER_ID, None) => "channel",
+            _ => "your",
+        };
+
+        self.language.format(&format!("{}.{}", key, owner), args)
+    }
+
+    fn channel_name(message: &Message) -> String {
+        message
+            .author_signature
+            .clone()
+            .or_else(|| message.chat.title.clone())
+            .unwrap_or_default()
+    }
+
+    fn username(user: &User) -> String {
+        match &user.username {
+            Some(name) => format!("@{}", name),
+            None => user.first_name.clone(),
+        }
+    }
+
+    pub async fn process(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        if let Some(callback_query_id) = &self.callback_query_id {
+            self.api
+                .answer_callback_query(callback_query_id.clone())
+                .await?;
+        }
+
+        if let Some(member_id) = self.channel_member_id {
+            if !self.is_admin(member_id).await? {
+                return Ok(None);
+            }
+        }
+
+        if self.chat.state == ClientState::Initial
+            && matches!(self.command, Command::UnknownCommand(_))
+        {
+            return Ok(None);
+        }
+
+        if self.location.is_some()
+            && self.command != Command::Location
+            && !self.waits_for_location()
+        {
+            return Ok(None);
+        }
+
+        // A button pressed on an old keyboard or by another member of the chat
+        // does not belong to any running command.
+        if matches!(self.command, Command::SelectCity(_)) && !self.waits_for_city_selection() {
+            return Ok(None);
+        }
+
+        if matches!(
+            self.command,
+            Command::EditForecast(_)
+                | Command::RemoveForecast(_)
+                | Command::Unfavorite(_)
+                | Command::NameFavorite(_)
+                | Command::SetLanguage(_)
+                | Command::ChooseGroupLanguage(_)
+                | Command::SetUnits(_)
+                | Command::ChooseGroupUnits(_)
+        ) && self.chat.state != ClientState::Initial
+        {
+            return Ok(None);
+        }
+
+        self.send_typing().await?;
+
+        // Cancel works in every state, the other commands only in the initial one.
+        if Command::Cancel == self.command {
+            return self.cancel_command().await;
+        }
+
+        match self.chat.state {
+            ClientState::Initial => self.process_initial().await,
+
+            ClientState::FindCity => {
+                self.process_find_city().await?;
+                Ok(None)
+            }
+
+            ClientState::SetCity => {
+                self.process_set_city().await?;
+                Ok(None)
+            }
+
+            ClientState::Time => {
+                self.process_time().await?;
+                Ok(None)
+            }
+
+            ClientState::FindCityNumber => {
+                self.process_find_city_number().await?;
+                Ok(None)
+            }
+
+            ClientState::SetCityNumber => {
+                self.process_set_city_number().await?;
+                Ok(None)
+            }
+
+            ClientState::Offset => {
+                self.process_offset().await?;
+                Ok(None)
+            }
+
+            ClientState::ScheduleCity => {
+                self.process_schedule_city().await?;
+                Ok(None)
+            }
+
+            ClientState::ScheduleCityNumber => {
+                self.process_schedule_city_number().await?;
+                Ok(None)
+            }
+
+            ClientState::AddFavorite => {
+                self.process_add_favorite().await?;
+                Ok(None)
+            }
+
+            ClientState::AddFavoriteNumber => {
+                self.process_add_favorite_number().await?;
+                Ok(None)
+            }
+
+            ClientState::RenameFavorite => {
+                self.process_rename_favorite().await?;
+                Ok(None)
+            }
+        }
+    }
+
+    // The flows asking for a city also take the nearest city to a location.
+    fn waits_for_location(&self) -> bool {
+        matches!(
+            self.chat.state,
+            ClientState::FindCity
+                | ClientState::SetCity
+                | ClientState::ScheduleCity
+                | ClientState::AddFavorite
+        )
+    }
+
+    // The flows asking for a city also offer the favorites as buttons.
+    fn waits_for_city_selection(&self) -> bool {
+        matches!(
+            self.chat.state,
+            ClientState::FindCity
+                | ClientState::SetCity
+                | ClientState::ScheduleCity
+                | ClientState::FindCityNumber
+                | ClientState::SetCityNumber
+                | ClientState::ScheduleCityNumber
+                | ClientState::AddFavoriteNumber
+        )
+    }
+
+    async fn process_initial(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        if let Some(definition) = registry::find_command(&self.command) {
+            return (definition.handler)(self).await;
+        }
+
+        // Commands coming from deep links, buttons and locations.
+        match self.command {
+            Command::StartWith(ref payload) => {
+                self.process_start_payload(payload).await?;
+                Ok(None)
+            }
+            Command::Unfavorite(favorite_id) => {
+                self.remove_favorite(favorite_id).await?;
+                Ok(None)
+            }
+            Command::NameFavorite(favorite_id) => {
+                self.select_favorite_to_rename(favorite_id).await?;
+                Ok(None)
+            }
+            Command::SetLanguage(language) => {
+                self.set_language(&self.chat, language).await?;
+                Ok(None)
+            }
+            Command::SetUnits(units) => {
+                self.set_units(&self.chat, units).await?;
+                Ok(None)
+            }
+            Command::EditForecast(forecast_id) => self.edit_forecast(forecast_id).await,
+            Command::RemoveForecast(forecast_id) => self.remove_forecast(forecast_id).await,
+            Command::Location => {
+                self.location_weather().await?;
+                Ok(None)
+            }
+            Command::ChooseGroupLanguage(_) | Command::ChooseGroupUnits(_) => {
+                self.process_group_command().await
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub(super) async fn start_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.start_message().await?;
+        Ok(None)
+    }
+
+    pub(super) async fn find_city_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        if self.arguments.is_some() {
+            self.find_city_now().await?;
+
+            return Ok(None);
+        }
+
+        self.repo
+            .modify_state(
+                self.chat.bot_id,
+                &self.chat.id,
+                self.chat.user_id,
+                ClientState::FindCity,
+            )
+            .await?;
+
+        self.find_city_message().await?;
+
+        Ok(None)
+    }
+
+    pub(super) async fn default_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        match self.chat.default_city_id {
+            Some(id) => {
+                let city = self.repo.search_city_by_id(&id).await?;
+
+                self.get_weather(city).await?;
+            }
+            None => {
+                self.set_city().await?;
+
+                self.not_default_message().await?;
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub(super) async fn set_default_city_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        if self.arguments.is_some() {
+            self.set_city_now().await?;
+        } else {
+            self.set_city().await?;
+        }
+
+        Ok(None)
+    }
+
+    pub(super) async fn current_default_city_command(
+        &self,
+    ) -> Result<Option<Vec<Forecast>>, BotError> {
+        let text = match self.chat.default_city_id {
+            Some(id) => match self.repo.search_city_by_id(&id).await {
+                Ok(city) => self.language.format("default_city_is", &[&city]),
+                Err(_) => self.language.text("no_default_city").to_string(),
+            },
+            None => self.language.text("no_default_city").to_string(),
+        };
+        self.send_message(&text).await?;
+
+        Ok(None)
+    }
+
+    pub(super) async fn favorites_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.favorites_weather().await?;
+        Ok(None)
+    }
+
+    pub(super) async fn add_favorite_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.add_favorite().await?;
+        Ok(None)
+    }
+
+    pub(super) async fn remove_favorite_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.favorites_keyboard(
+            self.language.text("select_favorite_to_remove"),
+            UNFAVORITE_CALLBACK_PREFIX,
+        )
+        .await?;
+        Ok(None)
+    }
+
+    pub(super) async fn rename_favorite_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.favorites_keyboard(
+            self.language.text("select_favorite_to_rename"),
+            RENAME_CALLBACK_PREFIX,
+        )
+        .await?;
+        Ok(None)
+    }
+
+    pub(super) async fn compare_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.compare().await?;
+        Ok(None)
+    }
+
+    pub(super) async fn schedule_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        if self.arguments.is_some() {
+            self.schedule_weather_now().await?;
+        } else {
+            self.schedule_weather().await?;
+        }
+
+        Ok(None)
+    }
+
+    pub(super) async fn unschedule_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.unschedule(&self.chat).await
+    }
+
+    pub(super) async fn schedules_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.list_forecasts().await?;
+        Ok(None)
+    }
+
+    pub(super) async fn pause_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.pause().await
+    }
+
+    pub(super) async fn resume_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.resume().await?;
+        Ok(None)
+    }
+
+    pub(super) async fn set_offset_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.set_offset().await?;
+        Ok(None)
+    }
+
+    pub(super) async fn current_offset_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        let text = match self.chat.offset {
+            Some(offset) => self.language.format("offset_is", &[&offset]),
+            None => self.language.text("no_offset").to_string(),
+        };
+        self.send_message(&text).await?;
+
+        Ok(None)
+    }
+
+    pub(super) async fn group_default_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.group_default().await?;
+        Ok(None)
+    }
+
+    pub(super) async fn live_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.start_live_message().await?;
+        Ok(None)
+    }
+
+    pub(super) async fn stop_live_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.stop_live_message().await?;
+        Ok(None)
+    }
+
+    pub(super) async fn language_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.language(&self.chat, LANGUAGE_CALLBACK_PREFIX).await?;
+        Ok(None)
+    }
+
+    pub(super) async fn units_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.units(&self.chat, UNITS_CALLBACK_PREFIX).await?;
+        Ok(None)
+    }
+
+    pub(super) async fn cancel_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        self.cancel(None).await?;
+        Ok(None)
+    }
+
+    async fn process_start_payload(&self, payload: &StartPayload) -> Result<(), BotError> {
+        let city_id = match payload {
+            StartPayload::Weather(id)
+            | StartPayload::SetDefault(id)
+            | StartPayload::Schedule(id) => *id,
+        };
+
+        let city = match self.repo.search_city_by_id(&city_id).await {
+            Ok(city) => city,
+            Err(BotDbError::CityNotFoundError) => return self.unknown_link_city_message().await,
+            Err(err) => return Err(err.into()),
+        };
+
+        match payload {
+            StartPayload::Weather(_) => self.get_weather(city).await,
+            StartPayload::SetDefault(_) => self.set_default_city(city).await,
+            StartPayload::Schedule(_) => match self.chat.offset {
+                None => {
+                    self.send_message(self.language.text("schedule_without_offset"))
+                        .await
+                }
+                Some(_) => self.select_schedule_city(&city).await,
+            },
+        }
+    }
+
+    async fn find_city_now(&self) -> Result<(), BotError> {
+        let query = self.arguments.clone().unwrap_or_default();
+
+        let resolved = self
+            .resolve_city(&query, ClientState::FindCity, ClientState::FindCityNumber)
+            .await?;
+
+        match resolved {
+            Some(city) => self.get_weather(city).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn set_city_now(&self) -> Result<(), BotError> {
+        let query = self.arguments.clone().unwrap_or_default();
+
+        let resolved = self
+            .resolve_city(&query, ClientState::SetCity, ClientState::SetCityNumber)
+            .await?;
+
+        match resolved {
+            Some(city) => self.set_default_city(city).await,
+            None => Ok(()),
+        }
+    }
+
+    // `/schedule Berlin weekdays 07:30` schedules right away, without the time only the time is asked.
+    async fn schedule_weather_now(&self) -> Result<(), BotError> {
+        let offset = match self.chat.offset {
+            Some(offset) => offset,
+            None => return self.schedule_weather().await,
+        };
+
+        let arguments = self.arguments.clone().unwrap_or_default();
+        let (query, schedule) = Self::split_schedule_arguments(&arguments);
+
+        let resolved = self
+            .resolve_city(
+                &query,
+                ClientState::ScheduleCity,
+                ClientState::ScheduleCityNumber,
+            )
+            .await?;
+
+        match (resolved, schedule) {
+            (Some(city), Some(schedule)) => self.schedule_forecast(offset, city.id, schedule).await,
+            (Some(city), None) => self.select_schedule_city(&city).await,
+            (None, _) => Ok(()),
+        }
+    }
+
+    // Returns the city when the query matches only one. Otherwise the interactive
+    // flow continues, selecting one of the matches or writing the city again.
+    async fn resolve_city(
+        &self,
+        query: &str,
+        city_state: ClientState,
+        number_state: ClientState,
+    ) -> Result<Option<City>, BotError> {
+        let mut cities = self.find_cities(query).await?;
+
+        if cities.len() == 1 {
+            return Ok(cities.pop());
+        }
+
+        let state = if cities.is_empty() || cities.len() > MAX_CITY_MATCHES {
+            let text = self
+                .language
+                .format("city_not_found_write_again", &[&query]);
+            self.send_message(&text).await?;
+
+            city_state
+        } else {
+            self.city_keyboard(&cities).await?;
+
+            number_state
+        };
+
+        self.repo
+            .modify_state(self.chat.bot_id, &self.chat.id, self.chat.user_id, state)
+            .await?;
+
+        Ok(None)
+    }
+
+    // Cities with exactly that name go first, otherwise the name is searched as a pattern.
+    async fn find_cities(&self, query: &str) -> Result<Vec<City>, BotError> {
+        let (name, country) = Self::split_city_query(query);
+
+        let cities = self.repo.get_cities_by_name(name, country).await?;
+
+        if !cities.is_empty() {
+            return Ok(cities);
+        }
+
+        let cities = self
+            .repo
+            .get_city_by_pattern(name)
+            .await?
+            .iter()
+            .map(Repo::record_to_city)
+            .collect();
+
+        Ok(cities)
+    }
+
+    // `/compare Madrid, Lisbon, Paris, FR` compares those cities, without cities the favorites.
+    async fn compare(&self) -> Result<(), BotError> {
+        let mut cities: Vec<(String, City)> = Vec::new();
+        let mut notes: Vec<String> = Vec::new();
+
+        match &self.arguments {
+            Some(arguments) => {
+                for query in comparison::split_cities(arguments) {
+                    let mut found = self.find_cities(&query).await?;
+
+                    match found.len() {
+                        0 => notes.push(self.language.format("compare_not_found", &[&query])),
+                        1 => {
+                            let city = found.pop().unwrap();
+                            cities.push((city.short_name(), city));
+                        }
+                        _ => notes.push(self.language.format("compare_ambiguous", &[&query])),
+                    }
+                }
+            }
+            None => {
+                let settings = self.settings();
+
+                let favorites = self
+                    .repo
+                    .get_favorites(settings.bot_id, &settings.id, settings.user_id)
+                    .await?;
+
+                for favorite in favorites.iter() {
+                    let city = self.repo.search_city_by_id(&favorite.city_id).await?;
+                    cities.push((Self::favorite_label(favorite, &city), city));
+                }
+            }
+        }
+
+        if cities.len() > MAX_COMPARED_CITIES {
+            notes.push(
+                self.language
+                    .format("compare_truncated", &[&MAX_COMPARED_CITIES]),
+            );
+            cities.truncate(MAX_COMPARED_CITIES);
+        }
+
+        let language = self.language;
+        let units = self.units;
+
+        let weather_client = WeatherApiClient::weather_client().await;
+
+        // The weather client blocks while it waits for the API, so every city is
+        // fetched on its own blocking thread and they are requested at the same time.
+        let handles: Vec<_> = cities
+            .into_iter()
+            .map(|(label, city)| {
+                task::spawn_blocking(move || {
+                    let forecast = weather_client.fetch_weekly_blocking(
+                        city.coord.lat,
+                        city.coord.lon,
+                        language,
+                        units,
+                    );
+
+                    match forecast.map(|forecast| CityComparison::new(label.clone(), &forecast)) {
+                        Ok(Some(comparison)) => Ok(comparison),
+                        Ok(None) => Err(label),
+                        Err(error) => {
+                            log::error!("Failed to fetch the weather of {}: {:?}", city, error);
+                            Err(label)
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let mut compared = Vec::new();
+
+        for handle in handles {
+            match handle.await {
+                Ok(Ok(comparison)) => compared.push(comparison),
+                Ok(Err(label)) => {
+                    notes.push(self.language.format("compare_unavailable", &[&label]))
+                }
+                Err(error) => log::error!("Failed to compare a city: {:?}", error),
+            }
+        }
+
+        let mut text = if compared.len() < 2 {
+            self.language.text("compare_usage").to_string()
+        } else {
+            comparison::comparison_table(&compared, self.language, self.units)?
+        };
+
+        for note in notes {
+            text.push_str(&format!("\n{}", note));
+        }
+
+        self.send_message(&text).await
+    }
+
+    // `Paris, FR` is Paris in France.
+    fn split_city_query(query: &str) -> (&str, Option<&str>) {
+        match query.rsplit_once(',') {
+            Some((name, country)) if !country.trim().is_empty() => {
+                (name.trim(), Some(country.trim()))
+            }
+            _ => (query.trim().trim_end_matches(','), None),
+        }
+    }
+
+    // The city goes first, then the time and the days in any order, `Berlin Sat,Sun 09:00`.
+    fn split_schedule_arguments(arguments: &str) -> (String, Option<DeliverySchedule>) {
+        let words: Vec<&str> = arguments.split_whitespace().collect();
+
+        let time_position = words
+            .iter()
+            .rposition(|word| DeliverySchedule::parse_time(word).is_some());
+
+        for split in 1..=time_position.unwrap_or(0) {
+            if let Some(schedule) = DeliverySchedule::parse(&words[split..].join(" ")) {
+                return (words[..split].join(" "), Some(schedule));
+            }
+        }
+
+        (words.join(" "), None)
+    }
+
+    // In groups the live message shows the group default city.
+    async fn start_live_message(&self) -> Result<(), BotError> {
+        let city_id = self
+            .group
+            .as_ref()
+            .and_then(|group| group.default_city_id)
+            .or(self.chat.default_city_id);
+
+        let city = match city_id {
+            Some(id) => self.repo.search_city_by_id(&id).await?,
+            None => {
+                return self
+                    .send_message(self.language.text("live_no_default_city"))
+                    .await
+            }
+        };
+
+        let weather_client = WeatherApiClient::weather_client().await;
+
+        let weather_info = weather_client
+            .fetch(city.coord.lat, city.coord.lon, self.language, self.units)
+            .await?;
+
+        let text = deliver::live_message_text(&city, &weather_info, self.language);
+
+        let message = self
+            .api
+            .send_message_without_reply(self.chat.id, text)
+            .await?
+            .result;
+
+        if let Err(err) = self
+            .api
+            .pin_chat_message(self.chat.id, message.message_id)
+            .await
+        {
+            log::error!("Failed to pin live message {:?}", err);
+
+            return self
+                .send_message(self.language.text("live_pin_failed"))
+                .await;
+        }
+
+        // Only one message of a chat is kept up to date.
+        if let Some(live_message) = self
+            .repo
+            .get_live_message(self.chat.bot_id, &self.chat.id)
+            .await?
+        {
+            self.unpin_live_message(live_message.message_id).await;
+        }
+
+        self.repo
+            .insert_live_message(
+                self.chat.bot_id,
+                &self.chat.id,
+                message.message_id,
+                &city.id,
+                self.language.code(),
+                self.units.code(),
+                deliver::next_live_refresh(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn stop_live_message(&self) -> Result<(), BotError> {
+        let live_message = match self
+            .repo
+            .get_live_message(self.chat.bot_id, &self.chat.id)
+            .await?
+        {
+            Some(live_message) => live_message,
+            None => {
+                return self
+                    .send_message(self.language.text("live_not_found"))
+                    .await
+            }
+        };
+
+        self.unpin_live_message(live_message.message_id).await;
+
+        self.repo
+            .delete_live_message(self.chat.bot_id, &self.chat.id, live_message.message_id)
+            .await?;
+
+        self.send_message(self.language.text("live_stopped")).await
+    }
+
+    // The message may have been deleted or unpinned already.
+    async fn unpin_live_message(&self, message_id: i32) {
+        if let Err(err) = self.api.unpin_chat_message(self.chat.id, message_id).await {
+            log::info!("Live message {} was not unpinned {:?}", message_id, err);
+        }
+    }
+
+    pub(super) async fn process_group_command(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        let group = match &self.group {
+            Some(group) => group,
+            None => {
+                self.not_group_message().await?;
+                return Ok(None);
+            }
+        };
+
+        if !self.is_admin(self.chat.user_id).await? {
+            self.send_message(self.language.text("group_admins_only"))
+                .await?;
+
+            return Ok(None);
+        }
+
+        match self.command {
+            Command::UnScheduleGroup => self.unschedule(group).await,
+            // The language and the units are chosen with a keyboard,
+            // the state of the admin does not change.
+            Command::SetGroupLanguage => {
+                self.language(group, GROUP_LANGUAGE_CALLBACK_PREFIX).await?;
+                Ok(None)
+            }
+            Command::ChooseGroupLanguage(language) => {
+                self.set_language(group, language).await?;
+                Ok(None)
+            }
+            Command::SetGroupUnits => {
+                self.units(group, GROUP_UNITS_CALLBACK_PREFIX).await?;
+                Ok(None)
+            }
+            Command::ChooseGroupUnits(units) => {
+                self.set_units(group, units).await?;
+                Ok(None)
+            }
+            Command::ScheduleGroup if group.offset.is_none() => {
+                self.send_message(self.language.text("group_schedule_without_offset"))
+                    .await?;
+
+                Ok(None)
+            }
+            _ => {
+                self.repo
+                    .modify_editing_group(self.chat.bot_id, &self.chat.id, self.chat.user_id, true)
+                    .await?;
+
+                match self.command {
+                    Command::SetGroupCity => self.set_city().await?,
+                    Command::SetGroupOffset => self.set_offset().await?,
+                    _ => self.schedule_city().await?,
+                }
+
+                Ok(None)
+            }
+        }
+    }
+
+    async fn is_admin(&self, user_id: u64) -> Result<bool, BotError> {
+        let member = self.api.get_chat_member(self.chat.id, user_id).await?;
+
+        Ok(matches!(
+            member.result,
+            ChatMember::Owner(_) | ChatMember::Administrator(_)
+        ))
+    }
+
+    async fn group_default(&self) -> Result<(), BotError> {
+        let group = match &self.group {
+            Some(group) => group,
+            None => return self.not_group_message().await,
+        };
+
+        match group.default_city_id {
+            Some(id) => {
+                let city = self.repo.search_city_by_id(&id).await?;
+
+                self.get_weather(city).await
+            }
+            None => {
+                self.send_message(self.language.text("group_no_default_city"))
+                    .await
+            }
+        }
+    }
+
+    async fn not_group_message(&self) -> Result<(), BotError> {
+        self.send_message(self.language.text("only_groups")).await
+    }
+
+    async fn unschedule(&self, settings: &Chat) -> Result<Option<Vec<Forecast>>, BotError> {
+        let vec = self
+            .repo
+            .delete_forecasts(settings.bot_id, &settings.id, settings.user_id)
+            .await?;
+
+        let text = self.owner_text("forecasts_unscheduled", settings, &[]);
+        self.send_message(&text).await?;
+        Ok(Some(vec))
+    }
+
+    async fn list_forecasts(&self) -> Result<(), BotError> {
+        let settings = self.settings();
+
+        let forecasts = self
+            .repo
+            .get_forecasts_by_user(settings.bot_id, &settings.id, settings.user_id)
+            .await?;
+
+        if forecasts.is_empty() {
+            return self
+                .send_message(self.language.text("no_scheduled_forecasts"))
+                .await;
+        }
+
+        let offset = settings.offset.unwrap_or_default();
+
+        let mut text = self.owner_text("scheduled_forecasts", settings, &[&offset]);
+        let mut buttons = Vec::new();
+
+        for (index, forecast) in forecasts.iter().enumerate() {
+            let city = self.repo.search_city_by_id(&forecast.city_id).await?;
+            let number = index + 1;
+
+            let last_delivery = match forecast.last_delivered_at {
+                Some(last_delivered_at) => Self::local_datetime(last_delivered_at, offset),
+                None => self.language.text("never_delivered").to_string(),
+            };
+
+            let next_delivery = match (forecast.paused, forecast.paused_until) {
+                (true, Some(paused_until)) => self.language.format(
+                    "paused_until",
+                    &[&Self::local_datetime(paused_until, offset)],
+                ),
+                (true, None) => self.language.text("paused").to_string(),
+                (false, _) => Self::local_datetime(forecast.next_delivery_at, offset),
+            };
+
+            text.push_str(&self.language.format(
+                "scheduled_forecast",
+                &[
+                    &number,
+                    &city.short_name(),
+                    &Self::delivery_time(&forecast.cron_expression, offset, self.language),
+                    &next_delivery,
+                    &last_delivery,
+                ],
+            ));
+
+            buttons.push(vec![
+                InlineKeyboardButton::builder()
+                    .text(self.language.format("edit_button", &[&number]))
+                    .callback_data(format!("{}{}", EDIT_CALLBACK_PREFIX, forecast.id))
+                    .build(),
+                InlineKeyboardButton::builder()
+                    .text(self.language.format("remove_button", &[&number]))
+                    .callback_data(format!("{}{}", REMOVE_CALLBACK_PREFIX, forecast.id))
+                    .build(),
+            ]);
+        }
+
+        let keyboard = InlineKeyboardMarkup::builder()
+            .inline_keyboard(buttons)
+            .build();
+
+        self.send_message_with_keyboard(&text, keyboard).await
+    }
+
+    fn delivery_time(cron_expression: &str, offset: i8, language: Language) -> String {
+        match DeliverySchedule::from_cron_expression(cron_expression, offset) {
+            Some(schedule) => schedule.describe(language),
+            None => cron_expression.to_string(),
+        }
+    }
+
+    fn local_datetime(datetime: DateTime<Utc>, offset: i8) -> String {
+        (datetime + Duration::hours(offset.into()))
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+
+    // The pending delivery with the old time is removed, the forecast is
+    // delivered as before if the edit is cancelled.
+    async fn edit_forecast(&self, forecast_id: i32) -> Result<Option<Vec<Forecast>>, BotError> {
+        let settings = self.settings();
+
+        let forecast = match self
+            .repo
+            .get_forecast_by_id(
+                settings.bot_id,
+                &settings.id,
+                settings.user_id,
+                &forecast_id,
+            )
+            .await?
+        {
+            Some(forecast) => forecast,
+            None => {
+                self.forecast_not_found_message().await?;
+                return Ok(None);
+            }
+        };
+
+        if settings.offset.is_none() {
+            self.schedule_weather().await?;
+            return Ok(None);
+        }
+
+        // The forecast is kept while the new time is asked, and then rescheduled.
+        self.ask_schedule_time(format!("{}{}", EDIT_CALLBACK_PREFIX, forecast.id))
+            .await?;
+
+        Ok(Some(vec![forecast]))
+    }
+
+    async fn remove_forecast(&self, forecast_id: i32) -> Result<Option<Vec<Forecast>>, BotError> {
+        let settings = self.settings();
+
+        let forecast = match self
+            .repo
+            .delete_forecast(
+                settings.bot_id,
+                &settings.id,
+                settings.user_id,
+                &forecast_id,
+            )
+            .await?
+        {
+            Some(forecast) => forecast,
+            None => {
+                self.forecast_not_found_message().await?;
+                return Ok(None);
+            }
+        };
+
+        let city = self.repo.search_city_by_id(&forecast.city_id).await?;
+
+        let text = self
+            .language
+            .format("forecast_removed", &[&city.short_name()]);
+        self.send_message(&text).await?;
+
+        Ok(Some(vec![forecast]))
+    }
+
+    // One line per favorite, the weather of a city is fetched once in a while at most.
+    async fn favorites_weather(&self) -> Result<(), BotError> {
+        let settings = self.settings();
+
+        let favorites = self
+            .repo
+            .get_favorites(settings.bot_id, &settings.id, settings.user_id)
+            .await?;
+
+        if favorites.is_empty() {
+            return self.no_favorites_message().await;
+        }
+
+        let weather_client = WeatherApiClient::weather_client().await;
+
+        let mut text = self.owner_text("favorite_cities", settings, &[]);
+
+        for favorite in favorites.iter() {
+            let city = self.repo.search_city_by_id(&favorite.city_id).await?;
+            let label = Self::favorite_label(favorite, &city);
+
+            let line = match weather_client
+                .fetch_cached(&city, self.language, self.units)
+                .await
+            {
+                Ok(weather) => {
+                    let description = weather
+                        .weather
+                        .first()
+                        .map(|info| info.description.as_str())
+                        .unwrap_or_default();
+
+                    self.language.format(
+                        "favorite_weather",
+                        &[
+                            &label,
+                            &weather.units.temperature(weather.main.temp),
+                            &description,
+                        ],
+                    )
+                }
+                Err(error) => {
+                    log::error!("Failed to fetch the weather of {}: {:?}", city, error);
+
+                    self.language
+                        .format("favorite_weather_unavailable", &[&label])
+                }
+            };
+
+            text.push_str(&line);
+        }
+
+        self.send_message(&text).await
+    }
+
+    // `/add_favorite Madrid` adds the city right away, otherwise it is asked.
+    async fn add_favorite(&self) -> Result<(), BotError> {
+        let settings = self.settings();
+
+        let favorites = self
+            .repo
+            .get_favorites(settings.bot_id, &settings.id, settings.user_id)
+            .await?;
+
+        if favorites.len() >= MAX_FAVORITES {
+            let text = self.language.format("favorites_limit", &[&MAX_FAVORITES]);
+
+            return self.send_message(&text).await;
+        }
+
+        if let Some(query) = &self.arguments {
+            let resolved = self
+                .resolve_city(
+                    query,
+                    ClientState::AddFavorite,
+                    ClientState::AddFavoriteNumber,
+                )
+                .await?;
+
+            return match resolved {
+                Some(city) => self.insert_favorite(city).await,
+                None => Ok(()),
+            };
+        }
+
+        self.repo
+            .modify_state(
+                self.chat.bot_id,
+                &self.chat.id,
+                self.chat.user_id,
+                ClientState::AddFavorite,
+            )
+            .await?;
+
+        self.send_message(self.language.text("add_favorite_question"))
+            .await
+    }
+
+    async fn process_add_favorite(&self) -> Result<(), BotError> {
+        if let Some(city) = self.nearest_city().await? {
+            self.return_to_initial().await?;
+
+            return self.insert_favorite(city).await;
+        }
+
+        self.find_city().await?;
+
+        self.repo
+            .modify_state(
+                self.chat.bot_id,
+                &self.chat.id,
+                self.chat.user_id,
+                ClientState::AddFavoriteNumber,
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn process_add_favorite_number(&self) -> Result<(), BotError> {
+        match self.command {
+            Command::SelectCity(city_id) => {
+                let city = self.repo.search_city_by_id(&city_id).await?;
+
+                self.return_to_initial().await?;
+
+                self.insert_favorite(city).await
+            }
+
+            _ => self.not_selected_city_message().await,
+        }
+    }
+
+    async fn insert_favorite(&self, city: City) -> Result<(), BotError> {
+        let settings = self.settings();
+
+        let favorite = self
+            .repo
+            .insert_favorite(settings.bot_id, &settings.id, settings.user_id, &city.id)
+            .await?;
+
+        let key = match favorite {
+            Some(_) => "favorite_added",
+            None => "favorite_exists",
+        };
+        let text = self.language.format(key, &[&city.short_name()]);
+
+        self.send_message(&text).await
+    }
+
+    async fn favorites_keyboard(&self, text: &str, prefix: &str) -> Result<(), BotError> {
+        let settings = self.settings();
+
+        let favorites = self
+            .repo
+            .get_favorites(settings.bot_id, &settings.id, settings.user_id)
+            .await?;
+
+        if favorites.is_empty() {
+            return self.no_favorites_message().await;
+        }
+
+        let mut buttons = Vec::new();
+
+        for favorite in favorites.iter() {
+            let city = self.repo.search_city_by_id(&favorite.city_id).await?;
+
+            buttons.push(vec![InlineKeyboardButton::builder()
+                .text(Self::favorite_label(favorite, &city))
+                .callback_data(format!("{}{}", prefix, favorite.id))
+                .build()]);
+        }
+
+        let keyboard = InlineKeyboardMarkup::builder()
+            .inline_keyboard(buttons)
+            .build();
+
+        self.send_message_with_keyboard(text, keyboard).await
+    }
+
+    async fn remove_favorite(&self, favorite_id: i32) -> Result<(), BotError> {
+        let settings = self.settings();
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let command_str = s.replace(BOT_NAME, "");
+        let favorite = match self
+            .repo
+            .delete_favorite(
+                settings.bot_id,
+                &settings.id,
+                settings.user_id,
+                &favorite_id,
+            )
+            .await?
+        {
+            Some(favorite) => favorite,
+            None => return self.favorite_not_found_message().await,
+        };
+
+        let city = self.repo.search_city_by_id(&favorite.city_id).await?;
+
+        let text = self.language.format(
+            "favorite_removed",
+            &[&Self::favorite_label(&favorite, &city)],
+        );
+
+        self.send_message(&text).await
+    }
+
+    async fn select_favorite_to_rename(&self, favorite_id: i32) -> Result<(), BotError> {
+        let settings = self.settings();
+
+        let favorites = self
+            .repo
+            .get_favorites(settings.bot_id, &settings.id, settings.user_id)
+            .await?;
 
-        let result = match command_str.trim() {
-            "/start" => Command::Start,
-            "/find_city" => Command::FindCity,
-            "/default" => Command::Default,
-            "/set_default_city" => Command::SetDefaultCity,
-            "/cancel" => Command::Cancel,
-            "/schedule" => Command::Schedule,
-            "/unschedule" => Command::UnSchedule,
-            "/set_offset" => Command::SetOffset,
-            "/current_default_city" => Command::CurrentDefaultCity,
-            "/current_offset" => Command::CurrentOffset,
-            _ => Command::UnknownCommand(command_str.to_string()),
+        let favorite = match favorites.iter().find(|favorite| favorite.id == favorite_id) {
+            Some(favorite) => favorite,
+            None => return self.favorite_not_found_message().await,
         };
 
-        Ok(result)
+        let city = self.repo.search_city_by_id(&favorite.city_id).await?;
+
+        self.repo
+            .modify_selected(
+                self.chat.bot_id,
+                &self.chat.id,
+                self.chat.user_id,
+                format!("{}", favorite.id),
+            )
+            .await?;
+
+        self.repo
+            .modify_state(
+                self.chat.bot_id,
+                &self.chat.id,
+                self.chat.user_id,
+                ClientState::RenameFavorite,
+            )
+            .await?;
+
+        let text = self.language.format(
+            "favorite_name_question",
+            &[&city.short_name(), &MAX_FAVORITE_NAME_LENGTH],
+        );
+
+        self.send_message(&text).await
     }
-}
 
-impl UpdateProcessor {
-    pub async fn create(update: Update) -> Result<Self, BotError> {
-        if let UpdateContent::Message(message) = &update.content {
-            if message.text.is_none() {
-                log::error!("Update doesn't contain any text {:?}", message);
+    async fn process_rename_favorite(&self) -> Result<(), BotError> {
+        let name = match Self::parse_favorite_name(&self.text) {
+            Some(name) => name,
+            None => {
+                let text = self
+                    .language
+                    .format("invalid_favorite_name", &[&MAX_FAVORITE_NAME_LENGTH]);
 
-                return Err(BotError::UpdateNotMessage("no text".to_string()));
+                return self.cancel(Some(text)).await;
             }
+        };
 
-            let text = message.text.clone().unwrap();
-
-            let repo = Repo::repo().await?;
-            let api = ApiClient::api_client().await;
+        let favorite_id = match self.chat.selected.as_deref().map(str::parse::<i32>) {
+            Some(Ok(favorite_id)) => favorite_id,
+            _ => return self.cancel(None).await,
+        };
 
-            let chat_id: i64 = message.chat.id;
-            let user = message.from.clone().expect("User not set");
-            let chat = repo.find_or_create_chat(&chat_id, user.id).await?;
-            let username = match user.username {
-                Some(name) => format!("@{}", name),
-                None => user.first_name,
-            };
+        let settings = self.settings();
 
-            let command = Command::from_str(&text).unwrap();
+        self.return_to_initial().await?;
 
-            let processor = Self::builder()
-                .repo(repo)
-                .api(api)
-                .message_id(message.message_id)
-                .text(text)
-                .username(username)
-                .chat(chat)
-                .command(command)
-                .build();
+        let favorite = self
+            .repo
+            .rename_favorite(
+                settings.bot_id,
+                &settings.id,
+                settings.user_id,
+                &favorite_id,
+                name,
+            )
+            .await?;
 
-            Ok(processor)
-        } else {
-            log::error!("Update is not a message {:?}", update);
+        match favorite {
+            Some(_) => {
+                let text = self.language.format("favorite_renamed", &[&name]);
 
-            Err(BotError::UpdateNotMessage("no message".to_string()))
+                self.send_message(&text).await
+            }
+            None => self.favorite_not_found_message().await,
         }
     }
 
-    pub async fn process(&self) -> Result<Option<Vec<Forecast>>, BotError> {
-        if self.chat.state == ClientState::Initial
-            && matches!(self.command, Command::UnknownCommand(_))
+    fn parse_favorite_name(text: &str) -> Option<&str> {
+        let name = text.trim();
+
+        if name.is_empty()
+            || name.starts_with('/')
+            || name.chars().count() > MAX_FAVORITE_NAME_LENGTH
         {
-            return Ok(None);
+            return None;
         }
 
-        self.send_typing().await?;
+        Some(name)
+    }
 
-        if Command::Cancel == self.command {
-            self.cancel(None).await?;
-            return Ok(None);
+    fn favorite_label(favorite: &Favorite, city: &City) -> String {
+        match &favorite.name {
+            Some(name) => format!("{} ({})", name, city.short_name()),
+            None => city.short_name(),
         }
+    }
 
-        match self.chat.state {
-            ClientState::Initial => self.process_initial().await,
+    async fn no_favorites_message(&self) -> Result<(), BotError> {
+        self.send_message(self.language.text("no_favorites")).await
+    }
 
-            ClientState::FindCity => {
-                self.process_find_city().await?;
-                Ok(None)
-            }
+    async fn favorite_not_found_message(&self) -> Result<(), BotError> {
+        self.send_message(self.language.text("favorite_not_found"))
+            .await
+    }
 
-            ClientState::SetCity => {
-                self.process_set_city().await?;
-                Ok(None)
-            }
+    // `/language es` sets the language right away, otherwise it is asked.
+    async fn language(&self, settings: &Chat, prefix: &str) -> Result<(), BotError> {
+        if let Some(arguments) = &self.arguments {
+            return match Language::from_code(arguments) {
+                Some(language) => self.set_language(settings, language).await,
+                None => {
+                    self.send_message(self.language.text("unknown_language"))
+                        .await
+                }
+            };
+        }
 
-            ClientState::Time => {
-                self.process_time().await?;
-                Ok(None)
-            }
+        let buttons = Language::ALL
+            .iter()
+            .map(|language| {
+                vec![InlineKeyboardButton::builder()
+                    .text(language.name())
+                    .callback_data(format!("{}{}", prefix, language.code()))
+                    .build()]
+            })
+            .collect();
+
+        let keyboard = InlineKeyboardMarkup::builder()
+            .inline_keyboard(buttons)
+            .build();
+
+        self.send_message_with_keyboard(self.language.text("language_question"), keyboard)
+            .await
+    }
 
-            ClientState::FindCityNumber => {
-                self.process_find_city_number().await?;
-                Ok(None)
-            }
+    async fn set_language(&self, settings: &Chat, language: Language) -> Result<(), BotError> {
+        self.repo
+            .modify_language(
+                settings.bot_id,
+                &settings.id,
+                settings.user_id,
+                language.code(),
+            )
+            .await?;
 
-            ClientState::SetCityNumber => {
-                self.process_set_city_number().await?;
-                Ok(None)
-            }
+        // Group members keep speaking their own language with the bot.
+        if self.group.is_some() && settings.user_id == SHARED_USER_ID {
+            let text = self
+                .language
+                .format("group_language_set", &[&language.name()]);
 
-            ClientState::Offset => {
-                self.process_offset().await?;
-                Ok(None)
-            }
+            return self.send_message(&text).await;
+        }
 
-            ClientState::ScheduleCity => {
-                self.process_schedule_city().await?;
-                Ok(None)
-            }
+        // The answer is already in the new language.
+        let text = language.format(
+            "greeting",
+            &[&self.username, &language.text("language_set")],
+        );
 
-            ClientState::ScheduleCityNumber => {
-                self.process_schedule_city_number().await?;
-                Ok(None)
-            }
-        }
+        self.api
+            .send_message(self.chat.id, self.message_id, text)
+            .await?;
+
+        Ok(())
     }
 
-    async fn process_initial(&self) -> Result<Option<Vec<Forecast>>, BotError> {
-        match self.command {
-            Command::FindCity => {
-                self.repo
-                    .modify_state(&self.chat.id, self.chat.user_id, ClientState::FindCity)
-                    .await?;
+    // `/units imperial` sets the units right away, otherwise they are asked.
+    async fn units(&self, settings: &Chat, prefix: &str) -> Result<(), BotError> {
+        if let Some(arguments) = &self.arguments {
+            return match Units::from_code(arguments) {
+                Some(units) => self.set_units(settings, units).await,
+                None => self.send_message(self.language.text("unknown_units")).await,
+            };
+        }
 
-                self.find_city_message().await?;
+        let buttons = Units::ALL
+            .iter()
+            .map(|units| {
+                vec![InlineKeyboardButton::builder()
+                    .text(units.to_string())
+                    .callback_data(format!("{}{}", prefix, units.code()))
+                    .build()]
+            })
+            .collect();
+
+        let keyboard = InlineKeyboardMarkup::builder()
+            .inline_keyboard(buttons)
+            .build();
+
+        self.send_message_with_keyboard(self.language.text("units_question"), keyboard)
+            .await
+    }
 
-                Ok(None)
-            }
-            Command::Start => {
-                self.start_message().await?;
-                Ok(None)
-            }
-            Command::CurrentDefaultCity => {
-                let text = match self.chat.default_city_id {
-                    Some(id) => match self.repo.search_city_by_id(&id).await {
-                        Ok(city) => format!("Your default city is {}", city),
-                        Err(_) => "You do not have default city".to_string(),
-                    },
-                    None => "You do not have default city".to_string(),
-                };
-                self.send_message(&text).await?;
+    async fn set_units(&self, settings: &Chat, units: Units) -> Result<(), BotError> {
+        self.repo
+            .modify_units(
+                settings.bot_id,
+                &settings.id,
+                settings.user_id,
+                units.code(),
+            )
+            .await?;
 
-                Ok(None)
-            }
-            Command::SetDefaultCity => {
-                self.set_city().await?;
-                Ok(None)
-            }
-            Command::Default => match self.chat.default_city_id {
-                Some(id) => {
-                    let city = self.repo.search_city_by_id(&id).await?;
+        let key = if self.group.is_some() && settings.user_id == SHARED_USER_ID {
+            "group_units_set"
+        } else {
+            "units_set"
+        };
+        let text = self.language.format(key, &[&units]);
 
-                    self.get_weather(city).await?;
+        self.send_message(&text).await
+    }
 
-                    Ok(None)
-                }
-                None => {
-                    self.set_city().await?;
+    // `/pause until 2026-08-31` pauses the forecasts until that date starts in the user timezone.
+    async fn pause(&self) -> Result<Option<Vec<Forecast>>, BotError> {
+        let settings = self.settings();
+        let offset = settings.offset.unwrap_or_default();
 
-                    self.not_default_message().await?;
+        let until = match &self.arguments {
+            Some(arguments) => match Self::parse_pause_end(arguments, offset) {
+                Some(until) if until > Utc::now() => Some(until),
+                _ => {
+                    self.send_message(self.language.text("invalid_pause_date"))
+                        .await?;
 
-                    Ok(None)
+                    return Ok(None);
                 }
             },
-            Command::Schedule => {
-                self.schedule_weather().await?;
-                Ok(None)
-            }
-            Command::SetOffset => {
-                self.set_offset().await?;
-                Ok(None)
-            }
-            Command::UnSchedule => self.unschedule().await,
-            _ => Ok(None),
+            None => None,
+        };
+
+        let forecasts = self
+            .repo
+            .pause_forecasts(settings.bot_id, &settings.id, settings.user_id, until)
+            .await?;
+
+        if forecasts.is_empty() {
+            self.send_message(self.language.text("no_scheduled_forecasts"))
+                .await?;
+
+            return Ok(None);
         }
+
+        let text = match until {
+            Some(until) => self.language.format(
+                "forecasts_paused_until",
+                &[&forecasts.len(), &Self::local_datetime(until, offset)],
+            ),
+            None => self
+                .language
+                .format("forecasts_paused", &[&forecasts.len()]),
+        };
+        self.send_message(&text).await?;
+
+        // Deliveries already queued are removed too.
+        Ok(Some(forecasts))
     }
 
-    async fn unschedule(&self) -> Result<Option<Vec<Forecast>>, BotError> {
-        let vec = self
+    fn parse_pause_end(arguments: &str, offset: i8) -> Option<DateTime<Utc>> {
+        let date = arguments.trim();
+        let date = date.strip_prefix("until").unwrap_or(date).trim();
+
+        let start_of_day = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .ok()?
+            .and_hms_opt(0, 0, 0)?;
+
+        Some(Utc.from_utc_datetime(&start_of_day) - Duration::hours(offset.into()))
+    }
+
+    async fn resume(&self) -> Result<(), BotError> {
+        let settings = self.settings();
+
+        let forecasts = self
             .repo
-            .delete_forecasts(&self.chat.id, self.chat.user_id)
+            .resume_forecasts(settings.bot_id, &settings.id, settings.user_id)
             .await?;
 
-        let text = "Your forecasts were unscheduled";
-        self.send_message(text).await?;
-        Ok(Some(vec))
+        let text = match forecasts.len() {
+            0 => self.language.text("no_paused_forecasts").to_string(),
+            n => self.language.format("forecasts_resumed", &[&n]),
+        };
+
+        self.send_message(&text).await
+    }
+
+    async fn forecast_not_found_message(&self) -> Result<(), BotError> {
+        self.send_message(self.language.text("forecast_not_found"))
+            .await
     }
 
     async fn process_schedule_city(&self) -> Result<(), BotError> {
-        self.find_city().await?;
+        if let Command::SelectCity(_) = self.command {
+            return self.process_schedule_city_number().await;
+        }
 
-        self.repo
-            .modify_selected(&self.chat.id, self.chat.user_id, self.text.clone())
-            .await?;
+        if let Some(city) = self.nearest_city().await? {
+            return self.select_schedule_city(&city).await;
+        }
+
+        self.find_city().await?;
 
         self.repo
             .modify_state(
+                self.chat.bot_id,
                 &self.chat.id,
                 self.chat.user_id,
                 ClientState::ScheduleCityNumber,
@@ -271,102 +1980,120 @@ impl UpdateProcessor {
     }
 
     async fn process_schedule_city_number(&self) -> Result<(), BotError> {
-        match self.text.parse::<usize>() {
-            Ok(number) => {
-                let city = self
-                    .repo
-                    .get_city_row(&self.chat.selected.clone().unwrap(), number)
-                    .await?;
-
-                self.repo
-                    .modify_selected(&self.chat.id, self.chat.user_id, format!("{}", city.id))
-                    .await?;
-
-                self.repo
-                    .modify_state(&self.chat.id, self.chat.user_id, ClientState::Time)
-                    .await?;
+        match self.command {
+            Command::SelectCity(city_id) => {
+                let city = self.repo.search_city_by_id(&city_id).await?;
 
-                self.schedule_weather_time_message().await
+                self.select_schedule_city(&city).await
             }
 
-            Err(_) => self.not_number_message().await,
+            _ => self.not_selected_city_message().await,
         }
     }
 
-    async fn process_find_city(&self) -> Result<(), BotError> {
-        self.find_city().await?;
+    async fn select_schedule_city(&self, city: &City) -> Result<(), BotError> {
+        self.ask_schedule_time(format!("{}", city.id)).await
+    }
 
+    // `selected` keeps the city to schedule, or the forecast being edited.
+    async fn ask_schedule_time(&self, selected: String) -> Result<(), BotError> {
         self.repo
-            .modify_selected(&self.chat.id, self.chat.user_id, self.text.clone())
+            .modify_selected(self.chat.bot_id, &self.chat.id, self.chat.user_id, selected)
             .await?;
 
         self.repo
             .modify_state(
+                self.chat.bot_id,
                 &self.chat.id,
                 self.chat.user_id,
-                ClientState::FindCityNumber,
+                ClientState::Time,
             )
             .await?;
 
-        Ok(())
+        self.schedule_weather_time_message().await
     }
 
-    async fn process_set_city(&self) -> Result<(), BotError> {
+    async fn process_find_city(&self) -> Result<(), BotError> {
+        if let Command::SelectCity(_) = self.command {
+            return self.process_find_city_number().await;
+        }
+
+        if self.location.is_some() {
+            self.return_to_initial().await?;
+
+            return self.location_weather().await;
+        }
+
         self.find_city().await?;
 
         self.repo
-            .modify_selected(&self.chat.id, self.chat.user_id, self.text.clone())
+            .modify_state(
+                self.chat.bot_id,
+                &self.chat.id,
+                self.chat.user_id,
+                ClientState::FindCityNumber,
+            )
             .await?;
 
+        Ok(())
+    }
+
+    async fn process_set_city(&self) -> Result<(), BotError> {
+        if let Command::SelectCity(_) = self.command {
+            return self.process_set_city_number().await;
+        }
+
+        if let Some(city) = self.nearest_city().await? {
+            self.return_to_initial().await?;
+
+            return self.set_default_city(city).await;
+        }
+
+        self.find_city().await?;
+
         self.repo
-            .modify_state(&self.chat.id, self.chat.user_id, ClientState::SetCityNumber)
+            .modify_state(
+                self.chat.bot_id,
+                &self.chat.id,
+                self.chat.user_id,
+                ClientState::SetCityNumber,
+            )
             .await?;
 
         Ok(())
     }
 
     async fn process_find_city_number(&self) -> Result<(), BotError> {
-        match self.text.parse::<usize>() {
-            Ok(number) => {
-                let city = self
-                    .repo
-                    .get_city_row(&self.chat.selected.clone().unwrap(), number)
-                    .await?;
+        match self.command {
+            Command::SelectCity(city_id) => {
+                let city = self.repo.search_city_by_id(&city_id).await?;
 
                 self.return_to_initial().await?;
 
                 self.get_weather(city).await
             }
 
-            Err(_) => self.not_number_message().await,
+            _ => self.not_selected_city_message().await,
         }
     }
 
     async fn process_set_city_number(&self) -> Result<(), BotError> {
-        match self.text.parse::<usize>() {
-            Ok(number) => {
-                let city = self
-                    .repo
-                    .get_city_row(&self.chat.selected.clone().unwrap(), number)
-                    .await?;
+        match self.command {
+            Command::SelectCity(city_id) => {
+                let city = self.repo.search_city_by_id(&city_id).await?;
 
                 self.return_to_initial().await?;
 
                 self.set_default_city(city).await
             }
 
-            Err(_) => self.not_number_message().await,
+            _ => self.not_selected_city_message().await,
         }
     }
 
     async fn not_valid_offset_message(&self) -> Result<(), BotError> {
-        self.cancel(Some(
-            "That's not a valid offset, it has to be a number in range [-11, 12].\n
-            If your timezone is UTC + 2 put 2, if you have UTC - 10 put -10, 0 if you have UTC timezone.\n
-            The command was cancelled"
-            .to_string(),
-        ))
-        .await?;
+        self.cancel(Some(self.language.text("invalid_offset").to_string()))
+            .await?;
 
         Ok(())
     }
@@ -375,27 +2102,19 @@ impl UpdateProcessor {
         &self,
         offset: i8,
         city_id: i32,
-        user_hour: i8,
-        minutes: i8,
+        schedule: DeliverySchedule,
     ) -> Result<(), BotError> {
-        let hour_utc = if user_hour - offset < 0 {
-            user_hour - offset + 24
-        } else if user_hour - offset > 24 {
-            user_hour - offset - 24
-        } else {
-            user_hour - offset
-        };
-
-        let cron_expression = format!("0 {} {} * * * *", minutes, hour_utc);
+        let cron_expression = schedule.cron_expression(offset);
 
         let datetime = Repo::calculate_next_delivery(&cron_expression)?;
 
-        // Here we should call repo.insert_forecast
-        // We have to ask city_id for now default city id set
+        let settings = self.settings();
+
         self.repo
-            .update_or_insert_forecast(
-                &self.chat.id,
-                self.chat.user_id,
+            .schedule_forecast(
+                settings.bot_id,
+                &settings.id,
+                settings.user_id,
                 &city_id,
                 cron_expression,
                 datetime,
@@ -404,15 +2123,47 @@ impl UpdateProcessor {
 
         self.return_to_initial().await?;
 
-        let minutes_pretty = if minutes < 10 {
-            format!("0{}", minutes)
-        } else {
-            minutes.to_string()
-        };
+        let text = self.language.format(
+            "forecast_scheduled",
+            &[&schedule.describe(self.language), &offset],
+        );
+
+        self.send_message(&text).await
+    }
+
+    async fn reschedule_forecast(
+        &self,
+        offset: i8,
+        forecast_id: i32,
+        schedule: DeliverySchedule,
+    ) -> Result<(), BotError> {
+        let cron_expression = schedule.cron_expression(offset);
+
+        let datetime = Repo::calculate_next_delivery(&cron_expression)?;
+
+        let settings = self.settings();
+
+        let forecast = self
+            .repo
+            .reschedule_forecast(
+                settings.bot_id,
+                &settings.id,
+                settings.user_id,
+                &forecast_id,
+                cron_expression,
+                datetime,
+            )
+            .await?;
+
+        self.return_to_initial().await?;
+
+        if forecast.is_none() {
+            return self.forecast_not_found_message().await;
+        }
 
-        let text = format!(
-            "Weather info scheduled every day at {}:{} UTC {}",
-            user_hour, minutes_pretty, offset
+        let text = self.language.format(
+            "forecast_scheduled",
+            &[&schedule.describe(self.language), &offset],
         );
 
         self.send_message(&text).await
@@ -425,13 +2176,15 @@ impl UpdateProcessor {
                     return self.not_valid_offset_message().await;
                 }
 
+                let settings = self.settings();
+
                 self.repo
-                    .modify_offset(&self.chat.id, self.chat.user_id, offset)
+                    .modify_offset(settings.bot_id, &settings.id, settings.user_id, offset)
                     .await?;
 
                 self.rechedule(offset).await?;
 
-                let text = format!("Your offset was set to {}", offset);
+                let text = self.owner_text("offset_set", settings, &[&offset]);
 
                 self.send_message(&text).await?;
 
@@ -443,42 +2196,31 @@ impl UpdateProcessor {
     }
 
     async fn rechedule(&self, new_offset: i8) -> Result<(), BotError> {
+        let settings = self.settings();
+
         let forecasts = self
             .repo
-            .get_forecasts_by_user(&self.chat.id, self.chat.user_id)
+            .get_forecasts_by_user(settings.bot_id, &settings.id, settings.user_id)
             .await?;
 
-        // If user has not forecasts this loop wont be executed.
-        for forecast in forecasts.into_iter() {
-            // previous offset it is fetched
-            let previous_offset: i8 = self.chat.offset.unwrap_or(0);
-
-            // get the time of the forecast with cron_expression and previous offset
-            // 0 {} {} * * * *
-            let old_cron_expression = forecast.cron_expression;
-            let vec: Vec<&str> = old_cron_expression.split(' ').collect();
-            let minutes_utc = vec[1].parse::<i8>().unwrap();
-            let old_hour_utc = vec[2].parse::<i8>().unwrap();
-
-            let user_hour = if old_hour_utc + previous_offset > 24 {
-                old_hour_utc + previous_offset - 24
-            } else if old_hour_utc + previous_offset < 0 {
-                old_hour_utc + previous_offset + 24
-            } else {
-                old_hour_utc + previous_offset
-            };
-
-            // Make the new cron_expression
+        // previous offset it is fetched
+        let previous_offset: i8 = settings.offset.unwrap_or(0);
 
-            let hour_utc = if user_hour - new_offset < 0 {
-                user_hour - new_offset + 24
-            } else if user_hour - new_offset > 24 {
-                user_hour - new_offset - 24
-            } else {
-                user_hour - new_offset
+        // If user has not forecasts this loop wont be executed.
+        for forecast in forecasts.into_iter() {
+            // The local time and days stay the same, the UTC ones move with the offset.
+            let schedule = match DeliverySchedule::from_cron_expression(
+                &forecast.cron_expression,
+                previous_offset,
+            ) {
+                Some(schedule) => schedule,
+                None => {
+                    log::error!("Unknown cron expression of forecast {:?}", forecast);
+                    continue;
+                }
             };
 
-            let new_cron_expression = format!("0 {} {} * * * *", minutes_utc, hour_utc);
+            let new_cron_expression = schedule.cron_expression(new_offset);
 
             // Update forecast
             let next_delivery = Repo::calculate_next_delivery(&new_cron_expression)?;
@@ -491,60 +2233,41 @@ impl UpdateProcessor {
     }
 
     async fn not_time_message(&self) -> Result<(), BotError> {
-        self.cancel(Some(
-            "That's not a well formatted time, it has to be formatted with this format `hour:minutes` being hour a number in range [0,23] 
-            and minutes a number in range [0,59]. The command was cancelled"
-            .to_string(),
-        ))
-        .await
-    }
-
-    fn parse_time(hour_or_minutes: &str, max_range: i8, min_range: i8) -> Result<i8, ()> {
-        match hour_or_minutes.parse::<i8>() {
-            Ok(number) => {
-                if !(min_range..=max_range).contains(&number) {
-                    Err(())
-                } else {
-                    Ok(number)
-                }
-            }
-            Err(_) => Err(()),
-        }
+        self.cancel(Some(self.language.text("invalid_time").to_string()))
+            .await
     }
 
     async fn process_time(&self) -> Result<(), BotError> {
-        let vec: Vec<&str> = self.text.trim().split(':').collect();
-
-        if vec.len() != 2 {
-            return self.not_time_message().await;
-        }
-
-        let hour = match Self::parse_time(vec[0], 23, 0) {
-            Err(_) => return self.not_time_message().await,
-            Ok(number) => number,
-        };
-
-        let minutes = match Self::parse_time(vec[1], 59, 0) {
-            Err(_) => return self.not_time_message().await,
-            Ok(number) => number,
+        let schedule = match DeliverySchedule::parse(&self.text) {
+            Some(schedule) => schedule,
+            None => return self.not_time_message().await,
         };
 
-        self.schedule_forecast(
-            self.chat.offset.unwrap(),
-            self.chat.selected.as_ref().unwrap().parse::<i32>().unwrap(),
-            hour,
-            minutes,
-        )
-        .await?;
+        let offset = self.settings().offset.unwrap();
+        let selected = self.chat.selected.as_deref().unwrap_or_default();
 
-        Ok(())
+        match selected
+            .strip_prefix(EDIT_CALLBACK_PREFIX)
+            .and_then(|id| id.parse::<i32>().ok())
+        {
+            Some(forecast_id) => {
+                self.reschedule_forecast(offset, forecast_id, schedule)
+                    .await
+            }
+            None => {
+                self.schedule_forecast(offset, selected.parse::<i32>().unwrap(), schedule)
+                    .await
+            }
+        }
     }
 
     async fn find_city(&self) -> Result<(), BotError> {
         let vec = self.repo.get_city_by_pattern(&self.text).await?;
 
-        if vec.is_empty() || vec.len() > 30 {
-            let text = format!("Your city {} was not found. Command cancelled.", self.text);
+        if vec.is_empty() || vec.len() > MAX_CITY_MATCHES {
+            let text = self
+                .language
+                .format("city_not_found_cancelled", &[&self.text]);
             self.send_message(&text).await?;
 
             // User state will get reverted after return this error.
@@ -553,20 +2276,57 @@ impl UpdateProcessor {
             return Err(BotError::DbError(BotDbError::CityNotFoundError));
         }
 
-        let mut i = 1;
-        let mut text: String = "I found these cities. Put a number to select one\n\n".to_string();
+        let cities: Vec<City> = vec.iter().map(Repo::record_to_city).collect();
 
-        for row in vec {
-            let name: String = row.get("name");
-            let country: String = row.get("country");
-            let state: String = row.get("state");
-            if state.is_empty() {
-                writeln!(&mut text, "{}. {},{}", i, name, country)?;
-            } else {
-                writeln!(&mut text, "{}. {},{},{}", i, name, country, state)?;
-            }
-            i += 1;
+        self.city_keyboard(&cities).await
+    }
+
+    async fn city_keyboard(&self, cities: &[City]) -> Result<(), BotError> {
+        let buttons: Vec<Vec<InlineKeyboardButton>> = cities
+            .iter()
+            .map(|city| {
+                let button = InlineKeyboardButton::builder()
+                    .text(city.short_name())
+                    .callback_data(format!("{}{}", CITY_CALLBACK_PREFIX, city.id))
+                    .build();
+
+                vec![button]
+            })
+            .collect();
+
+        let keyboard = InlineKeyboardMarkup::builder()
+            .inline_keyboard(buttons)
+            .build();
+
+        self.send_message_with_keyboard(self.language.text("select_city"), keyboard)
+            .await
+    }
+
+    async fn nearest_city(&self) -> Result<Option<City>, BotError> {
+        match &self.location {
+            Some(coord) => Ok(Some(self.repo.get_nearest_city(coord).await?)),
+            None => Ok(None),
         }
+    }
+
+    async fn location_weather(&self) -> Result<(), BotError> {
+        let coord = match &self.location {
+            Some(coord) => coord,
+            None => return Ok(()),
+        };
+
+        let city = self.repo.get_nearest_city(coord).await?;
+
+        let weather_client = WeatherApiClient::weather_client().await;
+
+        let weather_info = weather_client
+            .fetch(coord.lat, coord.lon, self.language, self.units)
+            .await?;
+
+        let text = self.language.format(
+            "location_weather",
+            &[&city.short_name(), &coord.lat, &coord.lon, &weather_info],
+        );
 
         self.send_message(&text).await
     }
@@ -576,7 +2336,7 @@ impl UpdateProcessor {
 
         let text = match custom_message {
             Some(message) => message,
-            None => "Your operation was canceled".to_string(),
+            None => self.language.text("operation_cancelled").to_string(),
         };
         self.send_message(&text).await
     }
@@ -586,131 +2346,188 @@ impl UpdateProcessor {
     }
 
     async fn _unknown_command(&self) -> Result<(), BotError> {
-        self.cancel(Some(
-            "Unknown command. See /start for available commands".to_string(),
-        ))
-        .await
+        self.cancel(Some(self.language.text("unknown_command").to_string()))
+            .await
     }
 
     async fn return_to_initial(&self) -> Result<(), BotError> {
         self.repo
-            .modify_state(&self.chat.id, self.chat.user_id, ClientState::Initial)
+            .modify_state(
+                self.chat.bot_id,
+                &self.chat.id,
+                self.chat.user_id,
+                ClientState::Initial,
+            )
             .await?;
 
+        if self.chat.editing_group {
+            self.repo
+                .modify_editing_group(self.chat.bot_id, &self.chat.id, self.chat.user_id, false)
+                .await?;
+        }
+
         Ok(())
     }
 
     async fn schedule_weather_time_message(&self) -> Result<(), BotError> {
-        let text =
-            "What time would you like to schedule ? (format hour:minutes in range 0-23:0-59)";
-
-        self.send_message(text).await
+        self.send_message(self.language.text("schedule_time_question"))
+            .await
     }
 
     async fn schedule_weather_message(&self) -> Result<(), BotError> {
-        let text = "What city would you like to schedule ?";
-
-        self.send_message(text).await
+        self.send_message_with_favorites(self.language.text("schedule_city_question"))
+            .await
     }
 
     async fn schedule_weather(&self) -> Result<(), BotError> {
         match self.chat.offset {
             None => {
                 // Just send message because it is in Initial state.
-                self.send_message(
-                    "Your can not schedule without offset set. Please execute /set_offset",
-                )
-                .await
-            }
-            Some(_) => {
-                self.repo
-                    .modify_state(&self.chat.id, self.chat.user_id, ClientState::ScheduleCity)
-                    .await?;
-
-                self.schedule_weather_message().await
+                self.send_message(self.language.text("schedule_without_offset"))
+                    .await
             }
+            Some(_) => self.schedule_city().await,
         }
     }
 
-    async fn set_offset(&self) -> Result<(), BotError> {
+    async fn schedule_city(&self) -> Result<(), BotError> {
         self.repo
-            .modify_state(&self.chat.id, self.chat.user_id, ClientState::Offset)
+            .modify_state(
+                self.chat.bot_id,
+                &self.chat.id,
+                self.chat.user_id,
+                ClientState::ScheduleCity,
+            )
             .await?;
 
-        let text = "Do you have any offset respect UTC ?\n 
-                (0 if your timezone is the same as UTC, 2 if UTC + 2 , -2 if UTC - 2, [-11,12])";
+        self.schedule_weather_message().await
+    }
+
+    async fn set_offset(&self) -> Result<(), BotError> {
+        self.repo
+            .modify_state(
+                self.chat.bot_id,
+                &self.chat.id,
+                self.chat.user_id,
+                ClientState::Offset,
+            )
+            .await?;
 
-        self.send_message(text).await
+        self.send_message(self.language.text("offset_question"))
+            .await
     }
 
     async fn set_city(&self) -> Result<(), BotError> {
         self.repo
-            .modify_state(&self.chat.id, self.chat.user_id, ClientState::SetCity)
+            .modify_state(
+                self.chat.bot_id,
+                &self.chat.id,
+                self.chat.user_id,
+                ClientState::SetCity,
+            )
             .await?;
 
         self.find_city_message().await
     }
 
-    async fn not_number_message(&self) -> Result<(), BotError> {
+    async fn not_selected_city_message(&self) -> Result<(), BotError> {
         self.cancel(Some(
-            "That's not a positive number in the range. The command was cancelled".to_string(),
+            self.language.text("select_city_from_list").to_string(),
         ))
         .await
     }
 
+    async fn unknown_link_city_message(&self) -> Result<(), BotError> {
+        self.send_message(self.language.text("unknown_link_city"))
+            .await
+    }
+
     async fn city_updated_message(&self) -> Result<(), BotError> {
-        let text = "Your default city was updated";
+        let text = self.owner_text("city_updated", self.settings(), &[]);
 
-        self.send_message(text).await
+        self.send_message(&text).await
     }
 
     async fn find_city_message(&self) -> Result<(), BotError> {
-        let text = "Write a city, let me see if I can find it";
+        self.send_message_with_favorites(self.language.text("write_city"))
+            .await
+    }
+
+    // Favorites are offered as quick picks, a written city works the same.
+    async fn send_message_with_favorites(&self, text: &str) -> Result<(), BotError> {
+        let settings = self.settings();
+
+        let favorites = self
+            .repo
+            .get_favorites(settings.bot_id, &settings.id, settings.user_id)
+            .await?;
+
+        if favorites.is_empty() {
+            return self.send_message(text).await;
+        }
+
+        let mut buttons = Vec::new();
 
-        self.send_message(text).await
+        for favorite in favorites.iter() {
+            let city = self.repo.search_city_by_id(&favorite.city_id).await?;
+
+            buttons.push(vec![InlineKeyboardButton::builder()
+                .text(format!("⭐ {}", Self::favorite_label(favorite, &city)))
+                .callback_data(format!("{}{}", CITY_CALLBACK_PREFIX, favorite.city_id))
+                .build()]);
+        }
+
+        let keyboard = InlineKeyboardMarkup::builder()
+            .inline_keyboard(buttons)
+            .build();
+
+        self.send_message_with_keyboard(text, keyboard).await
     }
 
     async fn start_message(&self) -> Result<(), BotError> {
-        let text = "This bot provides weather info around the globe.\nIn order to use it put the command:\n
-        /find_city Ask weather info from any city worldwide.\n
-        /set_default_city Set your default city.\n
-        /default Provides weather info from default city.\n
-        It would be really greatful if you take a look at my GitHub, look how much work I invested into this bot.\n
-        If you like this bot, consider giving me a star on GitHub or if you would like to self run it, fork the project please.\n
-        <a href=\"https://github.com/pxp9/weather_bot_rust\">RustWeatherBot GitHub repo</a>";
+        let text = registry::help_text(self.language)?;
 
-        self.send_message(text).await
+        self.send_message(&text).await
     }
 
     async fn get_weather(&self, city: City) -> Result<(), BotError> {
         let weather_client = WeatherApiClient::weather_client().await;
 
-        let weather_info = weather_client.fetch(city.coord.lat, city.coord.lon).await?;
+        let weather_info = weather_client
+            .fetch(city.coord.lat, city.coord.lon, self.language, self.units)
+            .await?;
 
-        let text = format!(
-            "{},{}\nLat {} , Lon {}\n{}",
-            city.name, city.country, city.coord.lat, city.coord.lon, weather_info,
+        let text = self.language.format(
+            "city_weather",
+            &[
+                &city.name,
+                &city.country,
+                &city.coord.lat,
+                &city.coord.lon,
+                &weather_info,
+            ],
         );
 
         self.send_message(&text).await
     }
 
     async fn set_default_city(&self, city: City) -> Result<(), BotError> {
+        let settings = self.settings();
+
         self.repo
-            .modify_default_city(&self.chat.id, self.chat.user_id, &city.id)
+            .modify_default_city(settings.bot_id, &settings.id, settings.user_id, &city.id)
             .await?;
 
         self.city_updated_message().await
     }
 
     async fn not_default_message(&self) -> Result<(), BotError> {
-        let text = "Setting default city...";
-
-        self.send_message(text).await
+        self.send_message(self.language.text("setting_default_city"))
+            .await
     }
 
     async fn send_message(&self, text: &str) -> Result<(), BotError> {
-        let text_with_username = format!("Hi, {}!\n{}", self.username, text);
+        let text_with_username = self.language.format("greeting", &[&self.username, &text]);
 
         self.api
             .send_message(self.chat.id, self.message_id, text_with_username)
@@ -718,6 +2535,20 @@ impl UpdateProcessor {
 
         Ok(())
     }
+    async fn send_message_with_keyboard(
+        &self,
+        text: &str,
+        keyboard: InlineKeyboardMarkup,
+    ) -> Result<(), BotError> {
+        let text_with_username = self.language.format("greeting", &[&self.username, &text]);
+
+        self.api
+            .send_message_with_keyboard(self.chat.id, self.message_id, text_with_username, keyboard)
+            .await?;
+
+        Ok(())
+    }
+
     async fn send_typing(&self) -> Result<(), BotError> {
         self.api.send_typing(self.chat.id).await?;
         Ok(())
@@ -725,8 +2556,89 @@ impl UpdateProcessor {
 }
 
 impl ProcessUpdateTask {
-    pub fn new(update: Update) -> Self {
-        Self { update }
+    pub fn new(bot_id: i64, update: Update) -> Self {
+        Self {
+            bot_id,
+            chat_id: Self::chat_id(&update),
+            update,
+        }
+    }
+
+    fn chat_id(update: &Update) -> Option<i64> {
+        match &update.content {
+            UpdateContent::Message(message) | UpdateContent::ChannelPost(message) => {
+                Some(message.chat.id)
+            }
+            UpdateContent::CallbackQuery(callback_query) => callback_query
+                .message
+                .as_ref()
+                .map(|message| message.chat.id),
+            _ => None,
+        }
+    }
+
+    // Service message telling that a message was pinned in the chat.
+    fn pinned_message(&self) -> Option<(i64, i32)> {
+        match &self.update.content {
+            UpdateContent::Message(message) | UpdateContent::ChannelPost(message) => message
+                .pinned_message
+                .as_ref()
+                .map(|pinned| (message.chat.id, pinned.message_id)),
+            _ => None,
+        }
+    }
+
+    // The live message is pinned by the bot itself, any other pin replaces it.
+    async fn stop_replaced_live_message(
+        &self,
+        repo: &Repo,
+        api: &ApiClient,
+        chat_id: i64,
+        message_id: i32,
+    ) -> Result<(), FangError> {
+        let live_message = match repo.get_live_message(api.bot_id(), &chat_id).await? {
+            Some(live_message) if live_message.message_id != message_id => live_message,
+            _ => return Ok(()),
+        };
+
+        log::info!(
+            "Message {} was pinned in chat {}, live message {} will not be refreshed anymore",
+            message_id,
+            chat_id,
+            live_message.message_id
+        );
+
+        repo.delete_live_message(api.bot_id(), &chat_id, live_message.message_id)
+            .await?;
+
+        Ok(())
+    }
+
+    // Marked once it is handled, so an update interrupted by a crash or
+    // a shutdown is processed again when its task is requeued.
+    async fn mark_processed(&self, repo: &Repo, api: &ApiClient) -> Result<(), FangError> {
+        repo.mark_update_processed(api.bot_id(), self.update.update_id)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn process_inline_query(&self, api: &'static ApiClient, inline_query: &InlineQuery) {
+        let processor = match InlineQueryProcessor::create(api, inline_query.clone()).await {
+            Ok(processor) => processor,
+            Err(err) => {
+                log::error!("Failed to initialize the inline query processor {:?}", err);
+                return;
+            }
+        };
+
+        if let Err(err) = processor.process().await {
+            log::error!(
+                "Failed to process the inline query {:?} - {:?}",
+                self.update,
+                err
+            );
+        }
     }
 }
 
@@ -734,12 +2646,43 @@ impl ProcessUpdateTask {
 #[async_trait]
 impl AsyncRunnable for ProcessUpdateTask {
     async fn run(&self, queueable: &mut dyn AsyncQueueable) -> Result<(), FangError> {
-        let processor = match UpdateProcessor::create(self.update.clone()).await {
+        let _running_task = shutdown::start_task()?;
+
+        let repo = Repo::repo().await?;
+        let api = ApiClient::api_client(self.bot_id).await?;
+
+        // Telegram resends updates and fang retries tasks, the same update may come twice.
+        if repo
+            .is_update_processed(api.bot_id(), self.update.update_id)
+            .await?
+        {
+            log::info!("Update {} was already processed", self.update.update_id);
+
+            return Ok(());
+        }
+
+        if let UpdateContent::InlineQuery(inline_query) = &self.update.content {
+            self.process_inline_query(api, inline_query).await;
+
+            return self.mark_processed(repo, api).await;
+        }
+
+        if let Some((chat_id, message_id)) = self.pinned_message() {
+            self.stop_replaced_live_message(repo, api, chat_id, message_id)
+                .await?;
+
+            return self.mark_processed(repo, api).await;
+        }
+
+        let processor = match UpdateProcessor::create(api, self.update.clone()).await {
             Ok(processor) => processor,
+            // Updates without a command are ignored.
+            Err(BotError::UpdateNotMessage(_)) => return self.mark_processed(repo, api).await,
+            // The chat could not be loaded, fang retries the update later.
             Err(err) => {
                 log::error!("Failed to initialize the processor {:?}", err);
 
-                return Ok(());
+                return Err(err.into());
             }
         };
 
@@ -762,6 +2705,7 @@ impl AsyncRunnable for ProcessUpdateTask {
                         .into_iter()
                         .map(|forecast| {
                             ScheduleWeatherTask::builder()
+                                .bot_id(forecast.bot_id)
                                 .cron_expression(forecast.cron_expression)
                                 .chat_id(forecast.chat_id)
                                 .user_id(forecast.user_id)
@@ -777,10 +2721,338 @@ impl AsyncRunnable for ProcessUpdateTask {
             }
         }
 
+        self.mark_processed(repo, api).await
+    }
+
+    // The later updates of the chat wait for the retries, they have to be few and short.
+    fn max_retries(&self) -> i32 {
+        UPDATE_RETRIES
+    }
+
+    fn backoff(&self, attempt: u32) -> u32 {
+        UPDATE_RETRY_SECONDS * (attempt + 1)
+    }
+
+    // Inline queries do not touch any chat state and are processed concurrently.
+    fn task_type(&self) -> String {
+        match self.chat_id {
+            Some(chat_id) => partition_task_type(chat_id.rem_euclid(CHAT_PARTITIONS.into()) as u32),
+            None => TASK_TYPE.to_string(),
+        }
+    }
+}
+
+pub fn partition_task_type(partition: u32) -> String {
+    format!("{}_{}", TASK_TYPE, partition)
+}
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+#[serde(crate = "fang::serde")]
+pub struct CleanProcessedUpdates;
+
+#[typetag::serde]
+#[async_trait]
+impl AsyncRunnable for CleanProcessedUpdates {
+    async fn run(&self, _queueable: &mut dyn AsyncQueueable) -> Result<(), FangError> {
+        // The next run cleans up instead.
+        let _running_task = match shutdown::start_task() {
+            Ok(running_task) => running_task,
+            Err(_) => return Ok(()),
+        };
+
+        let repo = Repo::repo().await?;
+
+        let before = Utc::now() - Duration::days(PROCESSED_UPDATES_RETENTION_DAYS);
+
+        let n = repo.delete_processed_updates(before).await?;
+
+        log::info!("CleanProcessedUpdates removed {} processed updates", n);
+
         Ok(())
     }
 
+    fn uniq(&self) -> bool {
+        true
+    }
+
     fn task_type(&self) -> String {
         TASK_TYPE.to_string()
     }
+
+    fn cron(&self) -> Option<Scheduled> {
+        Some(Scheduled::CronPattern(
+            CRON_CLEAN_PROCESSED_UPDATES.to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod process_update_task_test {
+    use super::*;
+
+    fn task(update: &str) -> ProcessUpdateTask {
+        ProcessUpdateTask::new(1, serde_json::from_str(update).unwrap())
+    }
+
+    fn message(update_id: u32, chat_id: i64) -> ProcessUpdateTask {
+        task(&format!(
+            r#"{{
+                "update_id": {},
+                "message": {{
+                    "message_id": 1,
+                    "date": 1441645532,
+                    "chat": {{"id": {}, "type": "group", "title": "Test"}},
+                    "from": {{"id": 1111111, "is_bot": false, "first_name": "Test"}},
+                    "text": "Madrid"
+                }}
+            }}"#,
+            update_id, chat_id
+        ))
+    }
+
+    #[test]
+    fn test_pinned_message() {
+        let pinned = task(
+            r#"{
+                "update_id": 5,
+                "message": {
+                    "message_id": 8,
+                    "date": 1441645532,
+                    "chat": {"id": -1002, "type": "group", "title": "Test"},
+                    "from": {"id": 1111111, "is_bot": false, "first_name": "Test"},
+                    "pinned_message": {
+                        "message_id": 7,
+                        "date": 1441645530,
+                        "chat": {"id": -1002, "type": "group", "title": "Test"},
+                        "text": "Hello"
+                    }
+                }
+            }"#,
+        );
+
+        assert_eq!(pinned.pinned_message(), Some((-1002, 7)));
+        assert_eq!(message(1, -1002).pinned_message(), None);
+    }
+
+    #[test]
+    fn test_updates_of_a_chat_share_partition() {
+        assert_eq!(message(1, -1002).task_type(), message(2, -1002).task_type());
+        assert_eq!(message(1, -1002).task_type(), partition_task_type(3));
+        assert_eq!(message(3, 1111111).task_type(), partition_task_type(1));
+
+        let inline_query = task(
+            r#"{
+                "update_id": 4,
+                "inline_query": {
+                    "id": "1",
+                    "from": {"id": 1111111, "is_bot": false, "first_name": "Test"},
+                    "query": "Madrid",
+                    "offset": ""
+                }
+            }"#,
+        );
+
+        assert_eq!(inline_query.task_type(), TASK_TYPE);
+    }
+
+    #[test]
+    fn test_parse_start_payload() {
+        assert_eq!(
+            Command::parse("/start city_3117735", "bot"),
+            Command::StartWith(StartPayload::Weather(3117735))
+        );
+        assert_eq!(
+            Command::parse("/start@bot default_2988507", "bot"),
+            Command::StartWith(StartPayload::SetDefault(2988507))
+        );
+        assert_eq!(
+            Command::parse("/start schedule_2950159", "bot"),
+            Command::StartWith(StartPayload::Schedule(2950159))
+        );
+        assert_eq!(Command::parse("/start city_madrid", "bot"), Command::Start);
+        assert_eq!(Command::parse("/start", "bot"), Command::Start);
+    }
+
+    #[test]
+    fn test_parse_command_arguments() {
+        assert_eq!(
+            Command::parse("/find_city Madrid", "bot"),
+            Command::FindCity
+        );
+        assert_eq!(
+            Command::arguments("/find_city@bot  Madrid ", "bot"),
+            Some("Madrid".to_string())
+        );
+        assert_eq!(Command::arguments("/find_city", "bot"), None);
+        assert_eq!(Command::arguments("Madrid", "bot"), None);
+    }
+
+    #[test]
+    fn test_location_command() {
+        assert_eq!(
+            Command::from_message("", true, ChatType::Private, "bot"),
+            Command::Location
+        );
+        assert_eq!(
+            Command::from_message("", true, ChatType::Group, "bot"),
+            Command::UnknownCommand("".to_string())
+        );
+        assert_eq!(
+            Command::from_message("/find_city", false, ChatType::Supergroup, "bot"),
+            Command::FindCity
+        );
+    }
+
+    #[test]
+    fn test_forecast_callback_data() {
+        assert_eq!(
+            Command::from_callback_data("edit:12"),
+            Command::EditForecast(12)
+        );
+        assert_eq!(
+            Command::from_callback_data("remove:12"),
+            Command::RemoveForecast(12)
+        );
+        assert_eq!(
+            Command::from_callback_data("remove:"),
+            Command::UnknownCommand("remove:".to_string())
+        );
+    }
+
+    #[test]
+    fn test_favorite_callback_data() {
+        assert_eq!(
+            Command::from_callback_data("unfavorite:3"),
+            Command::Unfavorite(3)
+        );
+        assert_eq!(
+            Command::from_callback_data("rename:3"),
+            Command::NameFavorite(3)
+        );
+    }
+
+    #[test]
+    fn test_units_callback_data() {
+        assert_eq!(
+            Command::from_callback_data("units:imperial"),
+            Command::SetUnits(Units::Imperial)
+        );
+        assert_eq!(
+            Command::from_callback_data("group_units:kelvin"),
+            Command::ChooseGroupUnits(Units::Kelvin)
+        );
+        assert_eq!(
+            Command::from_callback_data("units:rankine"),
+            Command::UnknownCommand("units:rankine".to_string())
+        );
+    }
+
+    #[test]
+    fn test_language_callback_data() {
+        assert_eq!(
+            Command::from_callback_data("language:es"),
+            Command::SetLanguage(Language::Spanish)
+        );
+        assert_eq!(
+            Command::from_callback_data("group_language:es"),
+            Command::ChooseGroupLanguage(Language::Spanish)
+        );
+        assert_eq!(
+            Command::from_callback_data("language:xx"),
+            Command::UnknownCommand("language:xx".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_favorite_name() {
+        assert_eq!(
+            UpdateProcessor::parse_favorite_name("  Home "),
+            Some("Home")
+        );
+        assert_eq!(UpdateProcessor::parse_favorite_name(" "), None);
+        assert_eq!(UpdateProcessor::parse_favorite_name("/favorites"), None);
+        assert_eq!(
+            UpdateProcessor::parse_favorite_name(&"a".repeat(MAX_FAVORITE_NAME_LENGTH + 1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_delivery_time() {
+        assert_eq!(
+            UpdateProcessor::delivery_time("0 5 22 * * * *", 2, Language::English),
+            "every day at 00:05"
+        );
+        assert_eq!(
+            UpdateProcessor::delivery_time(
+                "0 30 5 * * Mon,Tue,Wed,Thu,Fri *",
+                2,
+                Language::English
+            ),
+            "on weekdays at 07:30"
+        );
+        assert_eq!(
+            UpdateProcessor::delivery_time("0 30 1 * * Sat *", -3, Language::English),
+            "every Friday at 22:30"
+        );
+    }
+
+    #[test]
+    fn test_parse_pause_end() {
+        assert_eq!(
+            UpdateProcessor::parse_pause_end("until 2026-08-31", 2),
+            Some(Utc.ymd(2026, 8, 30).and_hms(22, 0, 0))
+        );
+        assert_eq!(
+            UpdateProcessor::parse_pause_end("2026-08-31", 0),
+            Some(Utc.ymd(2026, 8, 31).and_hms(0, 0, 0))
+        );
+        assert_eq!(UpdateProcessor::parse_pause_end("until tomorrow", 0), None);
+    }
+
+    #[test]
+    fn test_split_city_query() {
+        assert_eq!(
+            UpdateProcessor::split_city_query("Paris, FR"),
+            ("Paris", Some("FR"))
+        );
+        assert_eq!(
+            UpdateProcessor::split_city_query("Madrid"),
+            ("Madrid", None)
+        );
+        assert_eq!(
+            UpdateProcessor::split_city_query("Madrid,"),
+            ("Madrid", None)
+        );
+    }
+
+    #[test]
+    fn test_split_schedule_arguments() {
+        let split = |arguments| {
+            let (query, schedule) = UpdateProcessor::split_schedule_arguments(arguments);
+
+            (query, schedule.map(|schedule| schedule.to_string()))
+        };
+
+        assert_eq!(
+            split("Berlin 07:30"),
+            ("Berlin".to_string(), Some("every day at 07:30".to_string()))
+        );
+        assert_eq!(
+            split("New York, US 23:05 weekdays"),
+            (
+                "New York, US".to_string(),
+                Some("on weekdays at 23:05".to_string())
+            )
+        );
+        assert_eq!(
+            split("Berlin Sat,Sun 09:00"),
+            (
+                "Berlin".to_string(),
+                Some("on weekends at 09:00".to_string())
+            )
+        );
+        assert_eq!(split("Berlin 25:00"), ("Berlin 25:00".to_string(), None));
+        assert_eq!(split("Berlin"), ("Berlin".to_string(), None));
+    }
 }
@@ -0,0 +1,273 @@
+use super::process_update_task::Command;
+use super::process_update_task::UpdateProcessor;
+use crate::db::Forecast;
+use crate::locale::Language;
+use crate::telegram::client::ApiClient;
+use crate::telegram::client::ApiError;
+use crate::BotError;
+use frankenstein::BotCommand;
+use std::fmt::Write;
+use std::future::Future;
+use std::pin::Pin;
+
+pub type HandlerFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<Option<Vec<Forecast>>, BotError>> + Send + 'a>>;
+
+// Runs a command received in the initial state of the chat.
+pub type CommandHandler = for<'a> fn(&'a UpdateProcessor) -> HandlerFuture<'a>;
+
+pub struct CommandDefinition {
+    pub name: &'static str,
+    pub command: Command,
+    pub handler: CommandHandler,
+}
+
+// Single source of the bot commands. Parsing, dispatching, the /start help
+// text and the Telegram command menu are built from this list, the descriptions
+// are the `command_<name>` texts of the locale catalogs.
+pub static COMMANDS: &[CommandDefinition] = &[
+    CommandDefinition {
+        name: "start",
+        command: Command::Start,
+        handler: |processor| Box::pin(processor.start_command()),
+    },
+    CommandDefinition {
+        name: "find_city",
+        command: Command::FindCity,
+        handler: |processor| Box::pin(processor.find_city_command()),
+    },
+    CommandDefinition {
+        name: "default",
+        command: Command::Default,
+        handler: |processor| Box::pin(processor.default_command()),
+    },
+    CommandDefinition {
+        name: "set_default_city",
+        command: Command::SetDefaultCity,
+        handler: |processor| Box::pin(processor.set_default_city_command()),
+    },
+    CommandDefinition {
+        name: "current_default_city",
+        command: Command::CurrentDefaultCity,
+        handler: |processor| Box::pin(processor.current_default_city_command()),
+    },
+    CommandDefinition {
+        name: "favorites",
+        command: Command::Favorites,
+        handler: |processor| Box::pin(processor.favorites_command()),
+    },
+    CommandDefinition {
+        name: "add_favorite",
+        command: Command::AddFavorite,
+        handler: |processor| Box::pin(processor.add_favorite_command()),
+    },
+    CommandDefinition {
+        name: "remove_favorite",
+        command: Command::RemoveFavorite,
+        handler: |processor| Box::pin(processor.remove_favorite_command()),
+    },
+    CommandDefinition {
+        name: "rename_favorite",
+        command: Command::RenameFavorite,
+        handler: |processor| Box::pin(processor.rename_favorite_command()),
+    },
+    CommandDefinition {
+        name: "compare",
+        command: Command::Compare,
+        handler: |processor| Box::pin(processor.compare_command()),
+    },
+    CommandDefinition {
+        name: "schedule",
+        command: Command::Schedule,
+        handler: |processor| Box::pin(processor.schedule_command()),
+    },
+    CommandDefinition {
+        name: "unschedule",
+        command: Command::UnSchedule,
+        handler: |processor| Box::pin(processor.unschedule_command()),
+    },
+    CommandDefinition {
+        name: "schedules",
+        command: Command::Schedules,
+        handler: |processor| Box::pin(processor.schedules_command()),
+    },
+    CommandDefinition {
+        name: "pause",
+        command: Command::Pause,
+        handler: |processor| Box::pin(processor.pause_command()),
+    },
+    CommandDefinition {
+        name: "resume",
+        command: Command::Resume,
+        handler: |processor| Box::pin(processor.resume_command()),
+    },
+    CommandDefinition {
+        name: "set_offset",
+        command: Command::SetOffset,
+        handler: |processor| Box::pin(processor.set_offset_command()),
+    },
+    CommandDefinition {
+        name: "current_offset",
+        command: Command::CurrentOffset,
+        handler: |processor| Box::pin(processor.current_offset_command()),
+    },
+    CommandDefinition {
+        name: "group_default",
+        command: Command::GroupDefault,
+        handler: |processor| Box::pin(processor.group_default_command()),
+    },
+    CommandDefinition {
+        name: "set_group_city",
+        command: Command::SetGroupCity,
+        handler: |processor| Box::pin(processor.process_group_command()),
+    },
+    CommandDefinition {
+        name: "set_group_offset",
+        command: Command::SetGroupOffset,
+        handler: |processor| Box::pin(processor.process_group_command()),
+    },
+    CommandDefinition {
+        name: "set_group_language",
+        command: Command::SetGroupLanguage,
+        handler: |processor| Box::pin(processor.process_group_command()),
+    },
+    CommandDefinition {
+        name: "set_group_units",
+        command: Command::SetGroupUnits,
+        handler: |processor| Box::pin(processor.process_group_command()),
+    },
+    CommandDefinition {
+        name: "schedule_group",
+        command: Command::ScheduleGroup,
+        handler: |processor| Box::pin(processor.process_group_command()),
+    },
+    CommandDefinition {
+        name: "unschedule_group",
+        command: Command::UnScheduleGroup,
+        handler: |processor| Box::pin(processor.process_group_command()),
+    },
+    CommandDefinition {
+        name: "live",
+        command: Command::Live,
+        handler: |processor| Box::pin(processor.live_command()),
+    },
+    CommandDefinition {
+        name: "stop_live",
+        command: Command::StopLive,
+        handler: |processor| Box::pin(processor.stop_live_command()),
+    },
+    CommandDefinition {
+        name: "language",
+        command: Command::Language,
+        handler: |processor| Box::pin(processor.language_command()),
+    },
+    CommandDefinition {
+        name: "units",
+        command: Command::Units,
+        handler: |processor| Box::pin(processor.units_command()),
+    },
+    CommandDefinition {
+        name: "cancel",
+        command: Command::Cancel,
+        handler: |processor| Box::pin(processor.cancel_command()),
+    },
+];
+
+impl CommandDefinition {
+    pub fn description(&self, language: Language) -> &'static str {
+        language.text(&format!("command_{}", self.name))
+    }
+}
+
+pub fn find(name: &str) -> Option<&'static CommandDefinition> {
+    COMMANDS.iter().find(|definition| definition.name == name)
+}
+
+pub fn find_command(command: &Command) -> Option<&'static CommandDefinition> {
+    COMMANDS
+        .iter()
+        .find(|definition| definition.command == *command)
+}
+
+pub fn help_text(language: Language) -> Result<String, std::fmt::Error> {
+    let mut text = language.text("help_header").to_string();
+
+    for definition in COMMANDS.iter() {
+        writeln!(
+            &mut text,
+            "/{} {}",
+            definition.name,
+            definition.description(language)
+        )?;
+    }
+
+    text.push_str(language.text("help_footer"));
+
+    Ok(text)
+}
+
+pub fn bot_commands(language: Language) -> Vec<BotCommand> {
+    COMMANDS
+        .iter()
+        .map(|definition| {
+            BotCommand::builder()
+                .command(definition.name)
+                .description(definition.description(language))
+                .build()
+        })
+        .collect()
+}
+
+pub async fn register_commands() -> Result<(), ApiError> {
+    for api in ApiClient::api_clients().await?.iter() {
+        // Telegram shows the menu without a language to users of other languages.
+        api.set_my_commands(bot_commands(Language::default()), None)
+            .await?;
+
+        for language in Language::ALL {
+            api.set_my_commands(bot_commands(language), Some(language.code().to_string()))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod registry_test {
+    use super::*;
+
+    #[test]
+    fn test_every_command_is_described() {
+        for definition in COMMANDS.iter() {
+            let english = definition.description(Language::English);
+
+            assert!(
+                !english.is_empty(),
+                "/{} has no description",
+                definition.name
+            );
+
+            for language in Language::ALL {
+                if language != Language::English {
+                    assert_ne!(
+                        definition.description(language),
+                        english,
+                        "/{} has no {} description",
+                        definition.name,
+                        language.code()
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_command_has_its_own_handler() {
+        for definition in COMMANDS.iter() {
+            let found = find_command(&definition.command).unwrap();
+
+            assert_eq!(found.name, definition.name);
+        }
+    }
+}
@@ -3,33 +3,75 @@ use crate::open_weather_map::Coord;
 use crate::seeds::SeedCity;
 use crate::DATABASE_URL;
 use bb8_postgres::bb8::Pool;
+use bb8_postgres::bb8::PooledConnection;
 use bb8_postgres::bb8::RunError;
 use bb8_postgres::tokio_postgres::tls::NoTls;
 use bb8_postgres::tokio_postgres::Row;
+use bb8_postgres::tokio_postgres::Transaction;
 use bb8_postgres::PostgresConnectionManager;
 use cron::Schedule;
+use fang::AsyncRunnable;
 use fang::DateTime;
 use fang::FangError;
+use fang::Task;
 use fang::Utc;
 use postgres_types::{FromSql, ToSql};
 use std::include_str;
 use std::str::FromStr;
+use std::sync::RwLock;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::sync::OnceCell;
+use tokio::time::sleep;
+use tokio::time::Instant;
 use typed_builder::TypedBuilder;
 
 static REPO: OnceCell<Repo> = OnceCell::const_new();
 
+const CLOSE_CHECK_PERIOD: Duration = Duration::from_millis(50);
+
+// Degrees around a location where its nearest city is searched first.
+const NEAREST_CITY_RADIUS: f64 = 1.0;
+
+// The settings and forecasts shared by a whole group or channel are stored in
+// a chat row with this user id. Telegram never assigns it to a user.
+pub const SHARED_USER_ID: u64 = 0;
+
+// Bot id of the rows and tasks written before several bots could run in one process.
+pub const LEGACY_BOT_ID: i64 = 0;
+
+const ADOPT_LEGACY_CHATS: &str = include_str!("queries/adopt_legacy_chats.sql");
+const ADOPT_LEGACY_PROCESSED_UPDATES: &str =
+    include_str!("queries/adopt_legacy_processed_updates.sql");
+const ADOPT_LEGACY_UPDATE_OFFSETS: &str = include_str!("queries/adopt_legacy_update_offsets.sql");
+const ACTIVATE_CHAT: &str = include_str!("queries/activate_chat.sql");
+const DEACTIVATE_CHAT: &str = include_str!("queries/deactivate_chat.sql");
 const DELETE_CLIENT: &str = include_str!("queries/delete_client.sql");
+const DELETE_FAVORITE: &str = include_str!("queries/delete_favorite.sql");
+const DELETE_FORECAST: &str = include_str!("queries/delete_forecast.sql");
 const DELETE_FORECASTS: &str = include_str!("queries/delete_forecasts.sql");
+const DELETE_LIVE_MESSAGE: &str = include_str!("queries/delete_live_message.sql");
+const DELETE_PROCESSED_UPDATES: &str = include_str!("queries/delete_processed_updates.sql");
+const GET_FAVORITES: &str = include_str!("queries/get_favorites.sql");
+const GET_CITIES_BY_NAME: &str = include_str!("queries/get_cities_by_name.sql");
 const GET_CITY_BY_PATTERN: &str = include_str!("queries/get_city_by_pattern.sql");
+const GET_NEAREST_CITY: &str = include_str!("queries/get_nearest_city.sql");
 const INSERT_CLIENT: &str = include_str!("queries/insert_client.sql");
 const INSERT_CITY: &str = include_str!("queries/insert_city.sql");
+const INSERT_FAVORITE: &str = include_str!("queries/insert_favorite.sql");
 const INSERT_FORECAST: &str = include_str!("queries/insert_forecast.sql");
+const INSERT_LIVE_MESSAGE: &str = include_str!("queries/insert_live_message.sql");
 const UPDATE_FORECAST: &str = include_str!("queries/update_forecast.sql");
 const UPDATE_FORECAST_BY_USER: &str = include_str!("queries/update_forecast_by_user.sql");
+const RESCHEDULE_FORECAST: &str = include_str!("queries/reschedule_forecast.sql");
+const SCHEDULE_FORECAST: &str = include_str!("queries/schedule_forecast.sql");
+const UPDATE_LIVE_MESSAGE: &str = include_str!("queries/update_live_message.sql");
 const CHECK_USER_EXISTS: &str = include_str!("queries/check_user_exists.sql");
 const CHECK_CITIES_EXIST: &str = include_str!("queries/check_cities_exist.sql");
+const PAUSE_FORECASTS: &str = include_str!("queries/pause_forecasts.sql");
+const MODIFY_EDITING_GROUP: &str = include_str!("queries/modify_editing_group.sql");
+const MODIFY_LANGUAGE: &str = include_str!("queries/modify_language.sql");
+const MODIFY_UNITS: &str = include_str!("queries/modify_units.sql");
 const MODIFY_CITY: &str = include_str!("queries/modify_city.sql");
 const MODIFY_OFFSET: &str = include_str!("queries/modify_offset.sql");
 const MODIFY_SELECTED: &str = include_str!("queries/modify_selected.sql");
@@ -38,8 +80,26 @@ const SEARCH_CITY: &str = include_str!("queries/search_city.sql");
 const SEARCH_CITY_BY_ID: &str = include_str!("queries/search_city_by_id.sql");
 const GET_CHAT: &str = include_str!("queries/get_chat.sql");
 const GET_FORECAST: &str = include_str!("queries/get_forecast.sql");
+const GET_FORECAST_BY_ID: &str = include_str!("queries/get_forecast_by_id.sql");
 const GET_FORECASTS_BY_USER: &str = include_str!("queries/get_forecasts_by_user.sql");
 const GET_FORECASTS_BY_TIME: &str = include_str!("queries/get_forecasts_by_time.sql");
+const GET_EXPIRED_PAUSES: &str = include_str!("queries/get_expired_pauses.sql");
+const GET_PAUSED_FORECASTS: &str = include_str!("queries/get_paused_forecasts.sql");
+const GET_LIVE_MESSAGE: &str = include_str!("queries/get_live_message.sql");
+const GET_LIVE_MESSAGES_BY_TIME: &str = include_str!("queries/get_live_messages_by_time.sql");
+const GET_UPDATE_OFFSET: &str = include_str!("queries/get_update_offset.sql");
+const INSERT_PROCESSED_UPDATE: &str = include_str!("queries/insert_processed_update.sql");
+const CHECK_UPDATE_PROCESSED: &str = include_str!("queries/check_update_processed.sql");
+const FETCH_TASK: &str = include_str!("queries/fetch_task.sql");
+const PUT_BACK_TASK: &str = include_str!("queries/put_back_task.sql");
+const INSERT_UPDATE_TASK: &str = include_str!("queries/insert_update_task.sql");
+const SAVE_UPDATE_OFFSET: &str = include_str!("queries/save_update_offset.sql");
+const GET_SUSPENDED_FORECASTS: &str = include_str!("queries/get_suspended_forecasts.sql");
+const REQUEUE_INTERRUPTED_TASKS: &str = include_str!("queries/requeue_interrupted_tasks.sql");
+const RENAME_FAVORITE: &str = include_str!("queries/rename_favorite.sql");
+const RESUME_FORECAST: &str = include_str!("queries/resume_forecast.sql");
+const SUSPEND_FORECASTS: &str = include_str!("queries/suspend_forecasts.sql");
+const UNPAUSE_FORECAST: &str = include_str!("queries/unpause_forecast.sql");
 
 #[derive(Debug, Error)]
 pub enum BotDbError {
@@ -49,10 +109,14 @@ pub enum BotDbError {
     PgError(#[from] bb8_postgres::tokio_postgres::Error),
     #[error(transparent)]
     CronError(#[from] cron::error::Error),
+    #[error(transparent)]
+    SerdeError(#[from] serde_json::Error),
     #[error("City not found")]
     CityNotFoundError,
     #[error("No timestamps that match with this cron expression")]
     NoTimestampsError,
+    #[error("The database pool is closed")]
+    ClosedError,
 }
 
 impl From<BotDbError> for FangError {
@@ -83,42 +147,86 @@ pub enum ClientState {
     Time,
     #[postgres(name = "offset")]
     Offset,
+    #[postgres(name = "add_favorite")]
+    AddFavorite,
+    #[postgres(name = "add_favorite_number")]
+    AddFavoriteNumber,
+    #[postgres(name = "rename_favorite")]
+    RenameFavorite,
 }
 
-#[derive(Debug, Clone)]
+type Manager = PostgresConnectionManager<NoTls>;
+
+#[derive(Debug)]
 pub struct Repo {
-    pool: Pool<PostgresConnectionManager<NoTls>>,
+    // None once the repo is closed on shutdown.
+    pool: RwLock<Option<Pool<Manager>>>,
 }
 
 #[derive(Debug, Clone, TypedBuilder)]
 pub struct Chat {
+    pub bot_id: i64,
     pub id: i64,
     pub user_id: u64,
     pub state: ClientState,
     pub offset: Option<i8>,
     pub selected: Option<String>,
     pub default_city_id: Option<i32>,
+    pub active: bool,
+    pub editing_group: bool,
+    // Code of the language, None until it is known.
+    pub language: Option<String>,
+    // Code of the unit system, None for the default one.
+    pub units: Option<String>,
 }
 
 #[derive(Debug, Clone, TypedBuilder)]
 pub struct Forecast {
     pub id: i32,
+    pub bot_id: i64,
     pub chat_id: i64,
     pub user_id: u64,
     pub city_id: i32,
     pub cron_expression: String,
+    pub suspended: bool,
+    pub paused: bool,
+    pub paused_until: Option<DateTime<Utc>>,
     pub last_delivered_at: Option<DateTime<Utc>>,
     pub next_delivery_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub created_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct Favorite {
+    pub id: i32,
+    pub bot_id: i64,
+    pub chat_id: i64,
+    pub user_id: u64,
+    pub city_id: i32,
+    pub name: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct LiveMessage {
+    pub bot_id: i64,
+    pub chat_id: i64,
+    pub message_id: i32,
+    pub city_id: i32,
+    pub language: String,
+    pub units: String,
+    pub next_refresh_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
 impl Repo {
     pub async fn repo() -> Result<&'static Repo, BotDbError> {
         REPO.get_or_try_init(Repo::new).await
     }
 
-    async fn pool(url: &str) -> Result<Pool<PostgresConnectionManager<NoTls>>, BotDbError> {
+    async fn pool(url: &str) -> Result<Pool<Manager>, BotDbError> {
         let pg_mgr = PostgresConnectionManager::new_from_stringlike(url, NoTls)?;
 
         Ok(Pool::builder().build(pg_mgr).await?)
@@ -126,31 +234,73 @@ impl Repo {
 
     pub async fn new() -> Result<Self, BotDbError> {
         let pl = Self::pool(&DATABASE_URL).await?;
-        Ok(Repo { pool: pl })
+        Ok(Repo {
+            pool: RwLock::new(Some(pl)),
+        })
+    }
+
+    pub(crate) async fn connection(
+        &self,
+    ) -> Result<PooledConnection<'static, Manager>, BotDbError> {
+        let pool = self
+            .pool
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or(BotDbError::ClosedError)?;
+
+        Ok(pool.get_owned().await?)
+    }
+
+    /// Stops handing out connections, waits until the borrowed ones are
+    /// returned or the timeout expires and drops the pool, which closes them.
+    pub async fn close(&self, timeout: Duration) {
+        let pool = match self.pool.write().unwrap().take() {
+            Some(pool) => pool,
+            None => return,
+        };
+
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let state = pool.state();
+
+            if state.idle_connections == state.connections || Instant::now() >= deadline {
+                break;
+            }
+
+            sleep(CLOSE_CHECK_PERIOD).await;
+        }
     }
 
-    pub async fn check_user_exists(&self, chat_id: &i64, user_id: u64) -> Result<bool, BotDbError> {
-        let connection = self.pool.get().await?;
+    pub async fn check_user_exists(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+    ) -> Result<bool, BotDbError> {
+        let connection = self.connection().await?;
         let bytes = user_id.to_le_bytes().to_vec();
 
         let n = connection
-            .execute(CHECK_USER_EXISTS, &[chat_id, &bytes])
+            .execute(CHECK_USER_EXISTS, &[chat_id, &bytes, &bot_id])
             .await?;
         Ok(n == 1)
     }
 
     pub async fn find_or_create_chat(
         &self,
+        bot_id: i64,
         chat_id: &i64,
         user_id: u64,
     ) -> Result<Chat, BotDbError> {
-        if self.check_user_exists(chat_id, user_id).await? {
-            let chat = self.get_chat(chat_id, user_id).await?;
+        if self.check_user_exists(bot_id, chat_id, user_id).await? {
+            let chat = self.get_chat(bot_id, chat_id, user_id).await?;
 
             Ok(chat)
         } else {
-            self.insert_client(chat_id, user_id).await?;
-            let chat = self.get_chat(chat_id, user_id).await?;
+            self.insert_client(bot_id, chat_id, user_id).await?;
+            let chat = self.get_chat(bot_id, chat_id, user_id).await?;
 
             Ok(chat)
         }
@@ -158,39 +308,60 @@ impl Repo {
 
     pub async fn get_forecast(
         &self,
+        bot_id: i64,
         chat_id: &i64,
         user_id: u64,
         city_id: &i32,
     ) -> Result<Forecast, BotDbError> {
         let bytes = user_id.to_le_bytes().to_vec();
 
-        let connection = self.pool.get().await?;
+        let connection = self.connection().await?;
         let row = connection
-            .query_one(GET_FORECAST, &[chat_id, &bytes, city_id])
+            .query_one(GET_FORECAST, &[chat_id, &bytes, city_id, &bot_id])
             .await?;
 
         Ok(Self::row_to_forecast(row))
     }
 
+    // Forecasts are looked up with the chat and user too, so nobody
+    // can touch the forecasts of somebody else.
+    pub async fn get_forecast_by_id(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+        id: &i32,
+    ) -> Result<Option<Forecast>, BotDbError> {
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let connection = self.connection().await?;
+        let row = connection
+            .query_opt(GET_FORECAST_BY_ID, &[id, chat_id, &bytes, &bot_id])
+            .await?;
+
+        Ok(row.map(Self::row_to_forecast))
+    }
+
     pub async fn get_forecasts_by_user(
         &self,
+        bot_id: i64,
         chat_id: &i64,
         user_id: u64,
     ) -> Result<Vec<Forecast>, BotDbError> {
         let bytes = user_id.to_le_bytes().to_vec();
 
-        let connection = self.pool.get().await?;
+        let connection = self.connection().await?;
         let vec = connection
-            .query(GET_FORECASTS_BY_USER, &[chat_id, &bytes])
+            .query(GET_FORECASTS_BY_USER, &[chat_id, &bytes, &bot_id])
             .await?;
 
         Ok(vec.into_iter().map(Self::row_to_forecast).collect())
     }
 
-    pub async fn get_forecasts_by_time(&self) -> Result<Vec<Forecast>, BotDbError> {
-        let connection = self.pool.get().await?;
+    pub async fn get_forecasts_by_time(&self, bot_id: i64) -> Result<Vec<Forecast>, BotDbError> {
+        let connection = self.connection().await?;
         let vec = connection
-            .query(GET_FORECASTS_BY_TIME, &[&Utc::now()])
+            .query(GET_FORECASTS_BY_TIME, &[&Utc::now(), &bot_id])
             .await?;
 
         Ok(vec.into_iter().map(Self::row_to_forecast).collect())
@@ -222,12 +393,13 @@ impl Repo {
 
     pub async fn insert_forecast(
         &self,
+        bot_id: i64,
         chat_id: &i64,
         user_id: u64,
         city_id: &i32,
         cron_expression: String,
     ) -> Result<Forecast, BotDbError> {
-        let connection = self.pool.get().await?;
+        let connection = self.connection().await?;
 
         let bytes = user_id.to_le_bytes().to_vec();
 
@@ -243,6 +415,7 @@ impl Repo {
                     &cron_expression,
                     &next_delivery_at,
                     &Utc::now(),
+                    &bot_id,
                 ],
             )
             .await?;
@@ -255,6 +428,7 @@ impl Repo {
 
         Forecast::builder()
             .id(row.get("id"))
+            .bot_id(row.get("bot_id"))
             .chat_id(row.get("chat_id"))
             .user_id(user_id)
             .city_id(row.get("city_id"))
@@ -263,43 +437,119 @@ impl Repo {
             .updated_at(row.get("updated_at"))
             .created_at(row.get("created_at"))
             .cron_expression(row.get("cron_expression"))
+            .suspended(row.get("suspended"))
+            .paused(row.get("paused"))
+            .paused_until(row.get("paused_until"))
             .build()
     }
 
     pub async fn update_or_insert_forecast(
         &self,
+        bot_id: i64,
         chat_id: &i64,
         user_id: u64,
         city_id: &i32,
         cron_expression: String,
         next_delivery_at: DateTime<Utc>,
     ) -> Result<Forecast, BotDbError> {
-        let connection = self.pool.get().await?;
+        let connection = self.connection().await?;
 
         let bytes = user_id.to_le_bytes().to_vec();
 
         match connection
             .query_one(
                 UPDATE_FORECAST_BY_USER,
-                &[chat_id, &bytes, city_id, &Utc::now(), &next_delivery_at],
+                &[
+                    chat_id,
+                    &bytes,
+                    city_id,
+                    &Utc::now(),
+                    &next_delivery_at,
+                    &bot_id,
+                ],
             )
             .await
         {
             Ok(row) => Ok(Self::row_to_forecast(row)),
             Err(_) => {
-                self.insert_forecast(chat_id, user_id, city_id, cron_expression)
+                self.insert_forecast(bot_id, chat_id, user_id, city_id, cron_expression)
                     .await
             }
         }
     }
 
+    // Scheduling a city again replaces its schedule.
+    pub async fn schedule_forecast(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+        city_id: &i32,
+        cron_expression: String,
+        next_delivery_at: DateTime<Utc>,
+    ) -> Result<Forecast, BotDbError> {
+        let connection = self.connection().await?;
+
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let row = connection
+            .query_one(
+                SCHEDULE_FORECAST,
+                &[
+                    chat_id,
+                    &bytes,
+                    city_id,
+                    &cron_expression,
+                    &next_delivery_at,
+                    &Utc::now(),
+                    &bot_id,
+                ],
+            )
+            .await?;
+
+        Ok(Self::row_to_forecast(row))
+    }
+
+    // Returns None when the forecast does not exist or belongs to somebody else.
+    // The last delivery is kept, only the schedule changes.
+    pub async fn reschedule_forecast(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+        id: &i32,
+        cron_expression: String,
+        next_delivery_at: DateTime<Utc>,
+    ) -> Result<Option<Forecast>, BotDbError> {
+        let connection = self.connection().await?;
+
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let row = connection
+            .query_opt(
+                RESCHEDULE_FORECAST,
+                &[
+                    chat_id,
+                    &bytes,
+                    id,
+                    &cron_expression,
+                    &next_delivery_at,
+                    &Utc::now(),
+                    &bot_id,
+                ],
+            )
+            .await?;
+
+        Ok(row.map(Self::row_to_forecast))
+    }
+
     pub async fn update_forecast(
         &self,
         forecast_id: &i32,
         cron_expression: String,
         next_delivery_at: DateTime<Utc>,
     ) -> Result<u64, BotDbError> {
-        let connection = self.pool.get().await?;
+        let connection = self.connection().await?;
 
         Ok(connection
             .execute(
@@ -309,281 +559,1692 @@ impl Repo {
             .await?)
     }
 
-    pub async fn check_cities_exist(&self) -> Result<u64, BotDbError> {
-        let connection = self.pool.get().await?;
-        let n = connection.execute(CHECK_CITIES_EXIST, &[]).await?;
-        Ok(n)
-    }
-
-    pub async fn insert_city(&self, city: SeedCity) -> Result<u64, BotDbError> {
-        let connection = self.pool.get().await?;
+    // Chats that blocked the bot can not receive forecasts until they come back.
+    pub async fn deactivate_chat(&self, bot_id: i64, chat_id: &i64) -> Result<(), BotDbError> {
+        let mut connection = self.connection().await?;
+        let transaction = connection.transaction().await?;
 
-        let n = connection
-            .execute(
-                INSERT_CITY,
-                &[
-                    &city.name,
-                    &city.country,
-                    &city.state,
-                    &city.coord.lon,
-                    &city.coord.lat,
-                ],
-            )
+        transaction
+            .execute(DEACTIVATE_CHAT, &[chat_id, &bot_id])
             .await?;
-        Ok(n)
+        transaction
+            .execute(SUSPEND_FORECASTS, &[chat_id, &bot_id])
+            .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
     }
 
-    pub async fn search_city(
+    pub async fn activate_chat(
         &self,
-        name: &str,
-        country: &str,
-        state: &str,
-    ) -> Result<City, BotDbError> {
-        let connection = self.pool.get().await?;
+        bot_id: i64,
+        chat_id: &i64,
+    ) -> Result<Vec<Forecast>, BotDbError> {
+        let mut connection = self.connection().await?;
+        let transaction = connection.transaction().await?;
 
-        let vec: Vec<Row> = connection
-            .query(SEARCH_CITY, &[&name, &country, &state])
+        transaction
+            .execute(ACTIVATE_CHAT, &[chat_id, &bot_id])
             .await?;
-        if vec.len() == 1 {
-            Ok(Self::record_to_city(&vec[0]))
-        } else {
-            Err(BotDbError::CityNotFoundError)
-        }
-    }
 
-    pub async fn search_city_by_id(&self, id: &i32) -> Result<City, BotDbError> {
-        let connection = self.pool.get().await?;
+        let rows = transaction
+            .query(GET_SUSPENDED_FORECASTS, &[chat_id, &bot_id])
+            .await?;
 
-        let vec: Vec<Row> = connection.query(SEARCH_CITY_BY_ID, &[id]).await?;
-        if vec.len() == 1 {
-            Ok(Self::record_to_city(&vec[0]))
-        } else {
-            Err(BotDbError::CityNotFoundError)
+        let forecasts: Vec<Forecast> = rows.into_iter().map(Self::row_to_forecast).collect();
+
+        // Deliveries missed while the chat was inactive are skipped.
+        for forecast in forecasts.iter() {
+            let next_delivery_at = Self::calculate_next_delivery(&forecast.cron_expression)?;
+
+            transaction
+                .execute(RESUME_FORECAST, &[&forecast.id, &next_delivery_at])
+                .await?;
         }
+
+        transaction.commit().await?;
+
+        Ok(forecasts)
     }
 
-    pub async fn get_chat(&self, chat_id: &i64, user_id: u64) -> Result<Chat, BotDbError> {
-        let connection = self.pool.get().await?;
+    // Without `until` the forecasts stay paused until they are resumed.
+    pub async fn pause_forecasts(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Forecast>, BotDbError> {
+        let connection = self.connection().await?;
 
         let bytes = user_id.to_le_bytes().to_vec();
 
-        let row = connection.query_one(GET_CHAT, &[chat_id, &bytes]).await?;
+        let vec = connection
+            .query(PAUSE_FORECASTS, &[chat_id, &bytes, &until, &bot_id])
+            .await?;
 
-        let offset_bytes = row.try_get("offset").ok();
+        Ok(vec.into_iter().map(Self::row_to_forecast).collect())
+    }
 
-        let offset: Option<i8> = match offset_bytes {
-            Some(bytes) => {
-                let mut arr = [0u8; 1];
-                arr.copy_from_slice(bytes);
-                Some(i8::from_le_bytes(arr))
-            }
-            None => None,
-        };
+    pub async fn resume_forecasts(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+    ) -> Result<Vec<Forecast>, BotDbError> {
+        let mut connection = self.connection().await?;
+        let transaction = connection.transaction().await?;
 
-        let chat = Chat::builder()
-            .id(*chat_id)
-            .user_id(user_id)
-            .state(row.get("state"))
-            .selected(row.try_get("selected").ok())
-            .default_city_id(row.try_get("default_city_id").ok())
-            .offset(offset)
-            .build();
+        let bytes = user_id.to_le_bytes().to_vec();
 
-        Ok(chat)
-    }
+        let rows = transaction
+            .query(GET_PAUSED_FORECASTS, &[chat_id, &bytes, &bot_id])
+            .await?;
 
-    pub async fn insert_client(&self, chat_id: &i64, user_id: u64) -> Result<u64, BotDbError> {
-        let connection = self.pool.get().await?;
+        let forecasts = Self::unpause_forecasts(&transaction, rows).await?;
 
-        let bytes = user_id.to_le_bytes().to_vec();
+        transaction.commit().await?;
 
-        let n = connection
-            .execute(INSERT_CLIENT, &[chat_id, &ClientState::Initial, &bytes])
+        Ok(forecasts)
+    }
+
+    // Forecasts paused until a date that already passed.
+    pub async fn resume_expired_pauses(&self, bot_id: i64) -> Result<Vec<Forecast>, BotDbError> {
+        let mut connection = self.connection().await?;
+        let transaction = connection.transaction().await?;
+
+        let rows = transaction
+            .query(GET_EXPIRED_PAUSES, &[&Utc::now(), &bot_id])
             .await?;
-        Ok(n)
+
+        let forecasts = Self::unpause_forecasts(&transaction, rows).await?;
+
+        transaction.commit().await?;
+
+        Ok(forecasts)
     }
 
-    pub async fn delete_client(&self, chat_id: &i64, user_id: u64) -> Result<u64, BotDbError> {
-        let connection = self.pool.get().await?;
+    // Deliveries missed during the pause are skipped.
+    async fn unpause_forecasts(
+        transaction: &Transaction<'_>,
+        rows: Vec<Row>,
+    ) -> Result<Vec<Forecast>, BotDbError> {
+        let forecasts: Vec<Forecast> = rows.into_iter().map(Self::row_to_forecast).collect();
 
-        let bytes = user_id.to_le_bytes().to_vec();
+        for forecast in forecasts.iter() {
+            let next_delivery_at = Self::calculate_next_delivery(&forecast.cron_expression)?;
 
-        let n = connection
-            .execute(DELETE_CLIENT, &[chat_id, &bytes])
+            transaction
+                .execute(UNPAUSE_FORECAST, &[&forecast.id, &next_delivery_at])
+                .await?;
+        }
+
+        Ok(forecasts)
+    }
+
+    // A new live message of a chat replaces the previous one.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn insert_live_message(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        message_id: i32,
+        city_id: &i32,
+        language: &str,
+        units: &str,
+        next_refresh_at: DateTime<Utc>,
+    ) -> Result<LiveMessage, BotDbError> {
+        let connection = self.connection().await?;
+
+        let row = connection
+            .query_one(
+                INSERT_LIVE_MESSAGE,
+                &[
+                    chat_id,
+                    &message_id,
+                    city_id,
+                    &next_refresh_at,
+                    &Utc::now(),
+                    &language,
+                    &units,
+                    &bot_id,
+                ],
+            )
             .await?;
-        Ok(n)
+
+        Ok(Self::row_to_live_message(row))
     }
 
-    pub async fn delete_forecasts(
+    pub async fn get_live_message(
         &self,
+        bot_id: i64,
         chat_id: &i64,
-        user_id: u64,
-    ) -> Result<Vec<Forecast>, BotDbError> {
-        let connection = self.pool.get().await?;
+    ) -> Result<Option<LiveMessage>, BotDbError> {
+        let connection = self.connection().await?;
 
-        let bytes = user_id.to_le_bytes().to_vec();
+        let row = connection
+            .query_opt(GET_LIVE_MESSAGE, &[chat_id, &bot_id])
+            .await?;
 
-        let vec: Vec<Row> = connection
-            .query(DELETE_FORECASTS, &[chat_id, &bytes])
+        Ok(row.map(Self::row_to_live_message))
+    }
+
+    pub async fn get_live_messages_by_time(
+        &self,
+        bot_id: i64,
+    ) -> Result<Vec<LiveMessage>, BotDbError> {
+        let connection = self.connection().await?;
+        let vec = connection
+            .query(GET_LIVE_MESSAGES_BY_TIME, &[&Utc::now(), &bot_id])
             .await?;
 
-        Ok(vec.into_iter().map(Self::row_to_forecast).collect())
+        Ok(vec.into_iter().map(Self::row_to_live_message).collect())
     }
 
-    pub async fn modify_state(
+    pub async fn update_live_message(
         &self,
+        bot_id: i64,
         chat_id: &i64,
-        user_id: u64,
-        new_state: ClientState,
+        message_id: i32,
+        next_refresh_at: DateTime<Utc>,
     ) -> Result<u64, BotDbError> {
-        let connection = self.pool.get().await?;
-
-        let bytes = user_id.to_le_bytes().to_vec();
+        let connection = self.connection().await?;
 
         let n = connection
-            .execute(MODIFY_STATE, &[&new_state, chat_id, &bytes])
+            .execute(
+                UPDATE_LIVE_MESSAGE,
+                &[chat_id, &message_id, &next_refresh_at, &Utc::now(), &bot_id],
+            )
             .await?;
+
         Ok(n)
     }
 
-    pub async fn modify_default_city(
+    pub async fn delete_live_message(
         &self,
+        bot_id: i64,
         chat_id: &i64,
-        user_id: u64,
-        city_id: &i32,
+        message_id: i32,
     ) -> Result<u64, BotDbError> {
-        let connection = self.pool.get().await?;
-
-        let bytes = user_id.to_le_bytes().to_vec();
+        let connection = self.connection().await?;
 
         let n = connection
-            .execute(MODIFY_CITY, &[&city_id, chat_id, &bytes])
+            .execute(DELETE_LIVE_MESSAGE, &[chat_id, &message_id, &bot_id])
             .await?;
 
         Ok(n)
     }
 
-    pub async fn modify_offset(
+    fn row_to_live_message(row: Row) -> LiveMessage {
+        LiveMessage::builder()
+            .bot_id(row.get("bot_id"))
+            .chat_id(row.get("chat_id"))
+            .message_id(row.get("message_id"))
+            .city_id(row.get("city_id"))
+            .language(row.get("language"))
+            .units(row.get("units"))
+            .next_refresh_at(row.get("next_refresh_at"))
+            .updated_at(row.get("updated_at"))
+            .created_at(row.get("created_at"))
+            .build()
+    }
+
+    // Returns None when the city already is a favorite of the chat.
+    pub async fn insert_favorite(
         &self,
+        bot_id: i64,
         chat_id: &i64,
         user_id: u64,
-        offset: i8,
-    ) -> Result<u64, BotDbError> {
-        let connection = self.pool.get().await?;
-
-        let offset_bytes = offset.to_le_bytes();
+        city_id: &i32,
+    ) -> Result<Option<Favorite>, BotDbError> {
+        let connection = self.connection().await?;
 
         let bytes = user_id.to_le_bytes().to_vec();
 
-        let n = connection
-            .execute(MODIFY_OFFSET, &[&offset_bytes.as_slice(), chat_id, &bytes])
+        let row = connection
+            .query_opt(INSERT_FAVORITE, &[chat_id, &bytes, city_id, &bot_id])
             .await?;
 
-        Ok(n)
+        Ok(row.map(Self::row_to_favorite))
     }
 
-    pub async fn modify_selected(
+    pub async fn get_favorites(
         &self,
+        bot_id: i64,
         chat_id: &i64,
         user_id: u64,
-        new_selected: String,
-    ) -> Result<u64, BotDbError> {
-        let connection = self.pool.get().await?;
+    ) -> Result<Vec<Favorite>, BotDbError> {
+        let connection = self.connection().await?;
 
         let bytes = user_id.to_le_bytes().to_vec();
 
-        let n = connection
-            .execute(MODIFY_SELECTED, &[&new_selected, chat_id, &bytes])
+        let vec = connection
+            .query(GET_FAVORITES, &[chat_id, &bytes, &bot_id])
+            .await?;
+
+        Ok(vec.into_iter().map(Self::row_to_favorite).collect())
+    }
+
+    pub async fn delete_favorite(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+        id: &i32,
+    ) -> Result<Option<Favorite>, BotDbError> {
+        let connection = self.connection().await?;
+
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let row = connection
+            .query_opt(DELETE_FAVORITE, &[id, chat_id, &bytes, &bot_id])
+            .await?;
+
+        Ok(row.map(Self::row_to_favorite))
+    }
+
+    pub async fn rename_favorite(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+        id: &i32,
+        name: &str,
+    ) -> Result<Option<Favorite>, BotDbError> {
+        let connection = self.connection().await?;
+
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let row = connection
+            .query_opt(RENAME_FAVORITE, &[id, chat_id, &bytes, &bot_id, &name])
+            .await?;
+
+        Ok(row.map(Self::row_to_favorite))
+    }
+
+    fn row_to_favorite(row: Row) -> Favorite {
+        let user_id = Self::bytes_to_u64(row.get("user_id"));
+
+        Favorite::builder()
+            .id(row.get("id"))
+            .bot_id(row.get("bot_id"))
+            .chat_id(row.get("chat_id"))
+            .user_id(user_id)
+            .city_id(row.get("city_id"))
+            .name(row.get("name"))
+            .created_at(row.get("created_at"))
+            .build()
+    }
+
+    // The task of an update and its offset are written together, so polling
+    // resumes right after the last enqueued update.
+    pub async fn insert_update_task(
+        &self,
+        bot_id: i64,
+        task: &dyn AsyncRunnable,
+        update_id: u32,
+    ) -> Result<(), BotDbError> {
+        let mut connection = self.connection().await?;
+        let transaction = connection.transaction().await?;
+
+        let metadata = serde_json::to_value(task)?;
+
+        transaction
+            .execute(
+                INSERT_UPDATE_TASK,
+                &[&metadata, &task.task_type(), &Utc::now()],
+            )
+            .await?;
+
+        transaction
+            .execute(
+                SAVE_UPDATE_OFFSET,
+                &[&i64::from(update_id), &Utc::now(), &bot_id],
+            )
+            .await?;
+
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
+    // Rows written before several bots could run in one process belong to the first bot.
+    pub async fn adopt_legacy_rows(&self, bot_id: i64) -> Result<u64, BotDbError> {
+        let mut connection = self.connection().await?;
+        let transaction = connection.transaction().await?;
+
+        // Forecasts follow their chats through the ON UPDATE CASCADE foreign key.
+        let n = transaction.execute(ADOPT_LEGACY_CHATS, &[&bot_id]).await?;
+
+        transaction
+            .execute(ADOPT_LEGACY_UPDATE_OFFSETS, &[&bot_id])
+            .await?;
+        transaction
+            .execute(ADOPT_LEGACY_PROCESSED_UPDATES, &[&bot_id])
+            .await?;
+
+        transaction.commit().await?;
+
+        Ok(n)
+    }
+
+    // Returns a fetched task to the queue as it was, without counting an attempt.
+    pub async fn put_back_task(&self, task: &Task) -> Result<u64, BotDbError> {
+        let connection = self.connection().await?;
+
+        let n = connection
+            .execute(PUT_BACK_TASK, &[&task.id, &Utc::now()])
+            .await?;
+
+        Ok(n)
+    }
+
+    // Tasks left in progress by a previous run of this instance of the bot,
+    // which was killed before they finished. Tasks run by other instances are
+    // left alone, the ones without an instance were taken before it was recorded.
+    pub async fn requeue_interrupted_tasks(&self, worker_id: &str) -> Result<u64, BotDbError> {
+        let connection = self.connection().await?;
+
+        let n = connection
+            .execute(REQUEUE_INTERRUPTED_TASKS, &[&Utc::now(), &worker_id])
+            .await?;
+
+        Ok(n)
+    }
+
+    // Takes the oldest due task of the type like fang does. When `ordered`,
+    // a task waits while an earlier one of its chat is unfinished, for
+    // example waiting for its retry, so the tasks of a chat keep their order.
+    pub async fn fetch_task(
+        &self,
+        task_type: &str,
+        ordered: bool,
+        worker_id: &str,
+    ) -> Result<Option<Task>, BotDbError> {
+        let connection = self.connection().await?;
+
+        let row = connection
+            .query_opt(FETCH_TASK, &[&task_type, &Utc::now(), &ordered, &worker_id])
+            .await?;
+
+        Ok(row.map(|row| Task {
+            id: row.get("id"),
+            metadata: row.get("metadata"),
+            error_message: row.get("error_message"),
+            state: row.get("state"),
+            task_type: row.get("task_type"),
+            uniq_hash: row.get("uniq_hash"),
+            retries: row.get("retries"),
+            scheduled_at: row.get("scheduled_at"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        }))
+    }
+
+    pub async fn is_update_processed(
+        &self,
+        bot_id: i64,
+        update_id: u32,
+    ) -> Result<bool, BotDbError> {
+        let connection = self.connection().await?;
+
+        let row = connection
+            .query_one(CHECK_UPDATE_PROCESSED, &[&i64::from(update_id), &bot_id])
+            .await?;
+
+        Ok(row.get(0))
+    }
+
+    // Returns false when the update was already marked by another run.
+    pub async fn mark_update_processed(
+        &self,
+        bot_id: i64,
+        update_id: u32,
+    ) -> Result<bool, BotDbError> {
+        let connection = self.connection().await?;
+
+        let n = connection
+            .execute(
+                INSERT_PROCESSED_UPDATE,
+                &[&i64::from(update_id), &Utc::now(), &bot_id],
+            )
+            .await?;
+
+        Ok(n == 1)
+    }
+
+    pub async fn delete_processed_updates(&self, before: DateTime<Utc>) -> Result<u64, BotDbError> {
+        let connection = self.connection().await?;
+
+        let n = connection
+            .execute(DELETE_PROCESSED_UPDATES, &[&before])
+            .await?;
+
+        Ok(n)
+    }
+
+    pub async fn get_last_update_id(&self, bot_id: i64) -> Result<Option<u32>, BotDbError> {
+        let connection = self.connection().await?;
+
+        let row = connection.query_opt(GET_UPDATE_OFFSET, &[&bot_id]).await?;
+
+        Ok(row.map(|row| row.get::<_, i64>("update_id") as u32))
+    }
+
+    pub async fn check_cities_exist(&self) -> Result<u64, BotDbError> {
+        let connection = self.connection().await?;
+        let n = connection.execute(CHECK_CITIES_EXIST, &[]).await?;
+        Ok(n)
+    }
+
+    pub async fn insert_city(&self, city: SeedCity) -> Result<u64, BotDbError> {
+        let connection = self.connection().await?;
+
+        let n = connection
+            .execute(
+                INSERT_CITY,
+                &[
+                    &city.name,
+                    &city.country,
+                    &city.state,
+                    &city.coord.lon,
+                    &city.coord.lat,
+                ],
+            )
+            .await?;
+        Ok(n)
+    }
+
+    pub async fn search_city(
+        &self,
+        name: &str,
+        country: &str,
+        state: &str,
+    ) -> Result<City, BotDbError> {
+        let connection = self.connection().await?;
+
+        let vec: Vec<Row> = connection
+            .query(SEARCH_CITY, &[&name, &country, &state])
+            .await?;
+        if vec.len() == 1 {
+            Ok(Self::record_to_city(&vec[0]))
+        } else {
+            Err(BotDbError::CityNotFoundError)
+        }
+    }
+
+    pub async fn search_city_by_id(&self, id: &i32) -> Result<City, BotDbError> {
+        let connection = self.connection().await?;
+
+        let vec: Vec<Row> = connection.query(SEARCH_CITY_BY_ID, &[id]).await?;
+        if vec.len() == 1 {
+            Ok(Self::record_to_city(&vec[0]))
+        } else {
+            Err(BotDbError::CityNotFoundError)
+        }
+    }
+
+    pub async fn get_chat(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+    ) -> Result<Chat, BotDbError> {
+        let connection = self.connection().await?;
+
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let row = connection
+            .query_one(GET_CHAT, &[chat_id, &bytes, &bot_id])
+            .await?;
+
+        let offset_bytes = row.try_get("offset").ok();
+
+        let offset: Option<i8> = match offset_bytes {
+            Some(bytes) => {
+                let mut arr = [0u8; 1];
+                arr.copy_from_slice(bytes);
+                Some(i8::from_le_bytes(arr))
+            }
+            None => None,
+        };
+
+        let chat = Chat::builder()
+            .bot_id(bot_id)
+            .id(*chat_id)
+            .user_id(user_id)
+            .state(row.get("state"))
+            .selected(row.try_get("selected").ok())
+            .default_city_id(row.try_get("default_city_id").ok())
+            .offset(offset)
+            .active(row.get("active"))
+            .editing_group(row.get("editing_group"))
+            .language(row.get("language"))
+            .units(row.get("units"))
+            .build();
+
+        Ok(chat)
+    }
+
+    pub async fn insert_client(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+    ) -> Result<u64, BotDbError> {
+        let connection = self.connection().await?;
+
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let n = connection
+            .execute(
+                INSERT_CLIENT,
+                &[chat_id, &ClientState::Initial, &bytes, &bot_id],
+            )
+            .await?;
+        Ok(n)
+    }
+
+    pub async fn delete_client(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+    ) -> Result<u64, BotDbError> {
+        let connection = self.connection().await?;
+
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let n = connection
+            .execute(DELETE_CLIENT, &[chat_id, &bytes, &bot_id])
+            .await?;
+        Ok(n)
+    }
+
+    pub async fn delete_forecast(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+        id: &i32,
+    ) -> Result<Option<Forecast>, BotDbError> {
+        let connection = self.connection().await?;
+
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let row = connection
+            .query_opt(DELETE_FORECAST, &[id, chat_id, &bytes, &bot_id])
+            .await?;
+
+        Ok(row.map(Self::row_to_forecast))
+    }
+
+    pub async fn delete_forecasts(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+    ) -> Result<Vec<Forecast>, BotDbError> {
+        let connection = self.connection().await?;
+
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let vec: Vec<Row> = connection
+            .query(DELETE_FORECASTS, &[chat_id, &bytes, &bot_id])
+            .await?;
+
+        Ok(vec.into_iter().map(Self::row_to_forecast).collect())
+    }
+
+    pub async fn modify_state(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+        new_state: ClientState,
+    ) -> Result<u64, BotDbError> {
+        let connection = self.connection().await?;
+
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let n = connection
+            .execute(MODIFY_STATE, &[&new_state, chat_id, &bytes, &bot_id])
+            .await?;
+        Ok(n)
+    }
+
+    pub async fn modify_editing_group(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+        editing_group: bool,
+    ) -> Result<u64, BotDbError> {
+        let connection = self.connection().await?;
+
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let n = connection
+            .execute(
+                MODIFY_EDITING_GROUP,
+                &[&editing_group, chat_id, &bytes, &bot_id],
+            )
+            .await?;
+        Ok(n)
+    }
+
+    pub async fn modify_default_city(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+        city_id: &i32,
+    ) -> Result<u64, BotDbError> {
+        let connection = self.connection().await?;
+
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let n = connection
+            .execute(MODIFY_CITY, &[&city_id, chat_id, &bytes, &bot_id])
+            .await?;
+
+        Ok(n)
+    }
+
+    pub async fn modify_language(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+        language: &str,
+    ) -> Result<u64, BotDbError> {
+        let connection = self.connection().await?;
+
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let n = connection
+            .execute(MODIFY_LANGUAGE, &[&language, chat_id, &bytes, &bot_id])
+            .await?;
+
+        Ok(n)
+    }
+
+    pub async fn modify_units(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+        units: &str,
+    ) -> Result<u64, BotDbError> {
+        let connection = self.connection().await?;
+
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let n = connection
+            .execute(MODIFY_UNITS, &[&units, chat_id, &bytes, &bot_id])
+            .await?;
+
+        Ok(n)
+    }
+
+    pub async fn modify_offset(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+        offset: i8,
+    ) -> Result<u64, BotDbError> {
+        let connection = self.connection().await?;
+
+        let offset_bytes = offset.to_le_bytes();
+
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let n = connection
+            .execute(
+                MODIFY_OFFSET,
+                &[&offset_bytes.as_slice(), chat_id, &bytes, &bot_id],
+            )
+            .await?;
+
+        Ok(n)
+    }
+
+    pub async fn modify_selected(
+        &self,
+        bot_id: i64,
+        chat_id: &i64,
+        user_id: u64,
+        new_selected: String,
+    ) -> Result<u64, BotDbError> {
+        let connection = self.connection().await?;
+
+        let bytes = user_id.to_le_bytes().to_vec();
+
+        let n = connection
+            .execute(MODIFY_SELECTED, &[&new_selected, chat_id, &bytes, &bot_id])
             .await?;
 
         Ok(n)
     }
 
-    pub async fn get_city_by_pattern(&self, pattern: &str) -> Result<Vec<Row>, BotDbError> {
-        let connection = self.pool.get().await?;
+    pub async fn get_city_by_pattern(&self, pattern: &str) -> Result<Vec<Row>, BotDbError> {
+        let connection = self.connection().await?;
+
+        let pattern = format!("%{}%", pattern.to_uppercase());
+
+        let vec = connection.query(GET_CITY_BY_PATTERN, &[&pattern]).await?;
+        Ok(vec)
+    }
+
+    // Cities named exactly `name`, in `country` when it is given.
+    pub async fn get_cities_by_name(
+        &self,
+        name: &str,
+        country: Option<&str>,
+    ) -> Result<Vec<City>, BotDbError> {
+        let connection = self.connection().await?;
+
+        let vec = connection
+            .query(GET_CITIES_BY_NAME, &[&name, &country])
+            .await?;
+
+        Ok(vec.iter().map(Self::record_to_city).collect())
+    }
+
+    // Searches in a box of degrees around the location first, the whole table
+    // only when no city is that close.
+    pub async fn get_nearest_city(&self, coord: &Coord) -> Result<City, BotDbError> {
+        let connection = self.connection().await?;
+
+        for radius in [NEAREST_CITY_RADIUS, 180.0] {
+            let vec: Vec<Row> = connection
+                .query(GET_NEAREST_CITY, &[&coord.lat, &coord.lon, &radius])
+                .await?;
+
+            if let Some(row) = vec.first() {
+                return Ok(Self::record_to_city(row));
+            }
+        }
+
+        Err(BotDbError::CityNotFoundError)
+    }
+
+    pub fn record_to_city(record: &Row) -> City {
+        let coord = Coord::builder()
+            .lon(record.get("lon"))
+            .lat(record.get("lat"))
+            .build();
+
+        City::builder()
+            .id(record.get("id"))
+            .name(record.get("name"))
+            .country(record.get("country"))
+            .state(record.get("state"))
+            .coord(coord)
+            .build()
+    }
+}
+#[cfg(test)]
+mod db_test {
+    use crate::command::ProcessUpdateTask;
+    use crate::db::*;
+    use bb8_postgres::tokio_postgres::Row;
+
+    const BOT_ID: i64 = 1;
+
+    #[tokio::test]
+    async fn test_modify_state() {
+        // Pick a random user of the DB
+        let db_controller = Repo::new().await.unwrap();
+        let connection = db_controller.connection().await.unwrap();
+
+        let n = db_controller
+            .insert_client(BOT_ID, &111111, 1111111)
+            .await
+            .unwrap();
+
+        assert_eq!(n, 1_u64);
+
+        let row: &Row = &connection
+            .query_one("SELECT * FROM chats WHERE bot_id = $1 LIMIT 1", &[&BOT_ID])
+            .await
+            .unwrap();
+
+        let chat_id: i64 = row.get("id");
+
+        let bytes: &[u8] = row.get("user_id");
+        let mut arr = [0u8; 8];
+        arr.copy_from_slice(bytes);
+        let user_id = Repo::as_u64_le(&arr);
+
+        // testing modify state
+
+        let n = db_controller
+            .modify_state(BOT_ID, &chat_id, user_id, ClientState::FindCity)
+            .await
+            .unwrap();
+
+        assert_eq!(n, 1_u64);
+
+        // testing get state
+        let chat = db_controller
+            .get_chat(BOT_ID, &chat_id, user_id)
+            .await
+            .unwrap();
+
+        assert_eq!(chat.state, ClientState::FindCity);
+
+        let n = db_controller
+            .modify_state(BOT_ID, &chat_id, user_id, ClientState::Initial)
+            .await
+            .unwrap();
+        assert_eq!(n, 1_u64);
+
+        let chat = db_controller
+            .get_chat(BOT_ID, &chat_id, user_id)
+            .await
+            .unwrap();
+
+        assert_eq!(chat.state, ClientState::Initial);
+
+        let n = db_controller
+            .delete_client(BOT_ID, &111111, 1111111)
+            .await
+            .unwrap();
+        assert_eq!(n, 1_u64);
+    }
+
+    #[tokio::test]
+    async fn test_deactivate_and_activate_chat() {
+        let db_controller = Repo::new().await.unwrap();
+        let connection = db_controller.connection().await.unwrap();
+
+        let city = SeedCity {
+            name: "Test Deactivate".to_string(),
+            state: "".to_string(),
+            country: "ZZ".to_string(),
+            coord: Coord::builder().lat(0.0).lon(0.0).build(),
+        };
+        db_controller.insert_city(city).await.unwrap();
+        let city = db_controller
+            .search_city("Test Deactivate", "ZZ", "")
+            .await
+            .unwrap();
+
+        db_controller
+            .insert_client(BOT_ID, &222222, 2222222)
+            .await
+            .unwrap();
+        let forecast = db_controller
+            .insert_forecast(
+                BOT_ID,
+                &222222,
+                2222222,
+                &city.id,
+                "0 0 0 * * * *".to_string(),
+            )
+            .await
+            .unwrap();
+
+        db_controller
+            .deactivate_chat(BOT_ID, &222222)
+            .await
+            .unwrap();
+
+        let chat = db_controller
+            .get_chat(BOT_ID, &222222, 2222222)
+            .await
+            .unwrap();
+        assert!(!chat.active);
+
+        let forecast = db_controller
+            .get_forecast(BOT_ID, &222222, 2222222, &forecast.city_id)
+            .await
+            .unwrap();
+        assert!(forecast.suspended);
+
+        let restored = db_controller.activate_chat(BOT_ID, &222222).await.unwrap();
+        assert_eq!(restored.len(), 1);
+
+        let chat = db_controller
+            .get_chat(BOT_ID, &222222, 2222222)
+            .await
+            .unwrap();
+        assert!(chat.active);
+
+        let forecast = db_controller
+            .get_forecast(BOT_ID, &222222, 2222222, &forecast.city_id)
+            .await
+            .unwrap();
+        assert!(!forecast.suspended);
+
+        db_controller
+            .delete_forecasts(BOT_ID, &222222, 2222222)
+            .await
+            .unwrap();
+        db_controller
+            .delete_client(BOT_ID, &222222, 2222222)
+            .await
+            .unwrap();
+        connection
+            .execute("DELETE FROM cities WHERE id = $1", &[&city.id])
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_insert_update_task() {
+        let db_controller = Repo::new().await.unwrap();
+        let connection = db_controller.connection().await.unwrap();
+
+        let update = serde_json::from_str(
+            r#"{
+                "update_id": 900000,
+                "message": {
+                    "message_id": 1,
+                    "date": 1441645532,
+                    "chat": {"id": 444444, "type": "private", "first_name": "Test"},
+                    "from": {"id": 4444444, "is_bot": false, "first_name": "Test"},
+                    "text": "/start"
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let task = ProcessUpdateTask::new(BOT_ID, update);
+
+        db_controller
+            .insert_update_task(BOT_ID, &task, 900000)
+            .await
+            .unwrap();
+
+        // An older update never moves the offset back.
+        db_controller
+            .insert_update_task(BOT_ID, &task, 1)
+            .await
+            .unwrap();
+
+        let update_id = db_controller.get_last_update_id(BOT_ID).await.unwrap();
+        assert_eq!(update_id, Some(900000));
+
+        let n = connection
+            .execute(
+                "DELETE FROM fang_tasks WHERE metadata @> '{\"update\": {\"update_id\": 900000}}'",
+                &[],
+            )
+            .await
+            .unwrap();
+        assert_eq!(n, 2);
+    }
+
+    #[tokio::test]
+    async fn test_mark_update_processed() {
+        let db_controller = Repo::new().await.unwrap();
+
+        assert!(!db_controller
+            .is_update_processed(BOT_ID, 800000)
+            .await
+            .unwrap());
+
+        assert!(db_controller
+            .mark_update_processed(BOT_ID, 800000)
+            .await
+            .unwrap());
+        assert!(!db_controller
+            .mark_update_processed(BOT_ID, 800000)
+            .await
+            .unwrap());
+        assert!(db_controller
+            .is_update_processed(BOT_ID, 800000)
+            .await
+            .unwrap());
+
+        let n = db_controller
+            .delete_processed_updates(Utc::now() + chrono::Duration::seconds(1))
+            .await
+            .unwrap();
+        assert!(n >= 1);
+
+        assert!(db_controller
+            .mark_update_processed(BOT_ID, 800000)
+            .await
+            .unwrap());
+
+        db_controller
+            .delete_processed_updates(Utc::now() + chrono::Duration::seconds(1))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_group_settings() {
+        let db_controller = Repo::new().await.unwrap();
+
+        let group = db_controller
+            .find_or_create_chat(BOT_ID, &-333333, SHARED_USER_ID)
+            .await
+            .unwrap();
+        let member = db_controller
+            .find_or_create_chat(BOT_ID, &-333333, 3333333)
+            .await
+            .unwrap();
+
+        assert!(!member.editing_group);
+
+        db_controller
+            .modify_editing_group(BOT_ID, &member.id, member.user_id, true)
+            .await
+            .unwrap();
+        db_controller
+            .modify_offset(BOT_ID, &group.id, group.user_id, 2)
+            .await
+            .unwrap();
+
+        let member = db_controller
+            .get_chat(BOT_ID, &-333333, 3333333)
+            .await
+            .unwrap();
+        assert!(member.editing_group);
+        assert_eq!(member.offset, None);
+
+        let group = db_controller
+            .get_chat(BOT_ID, &-333333, SHARED_USER_ID)
+            .await
+            .unwrap();
+        assert!(!group.editing_group);
+        assert_eq!(group.offset, Some(2));
+
+        db_controller
+            .delete_client(BOT_ID, &-333333, 3333333)
+            .await
+            .unwrap();
+        db_controller
+            .delete_client(BOT_ID, &-333333, SHARED_USER_ID)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_chats_are_scoped_by_bot() {
+        let db_controller = Repo::new().await.unwrap();
+
+        db_controller
+            .insert_client(BOT_ID, &555555, 5555555)
+            .await
+            .unwrap();
+        db_controller
+            .insert_client(2, &555555, 5555555)
+            .await
+            .unwrap();
+
+        db_controller
+            .modify_offset(BOT_ID, &555555, 5555555, 3)
+            .await
+            .unwrap();
+
+        let chat = db_controller
+            .get_chat(BOT_ID, &555555, 5555555)
+            .await
+            .unwrap();
+        assert_eq!(chat.offset, Some(3));
+        assert_eq!(chat.language, None);
+
+        db_controller
+            .modify_language(BOT_ID, &555555, 5555555, "es")
+            .await
+            .unwrap();
+
+        let chat = db_controller
+            .get_chat(BOT_ID, &555555, 5555555)
+            .await
+            .unwrap();
+        assert_eq!(chat.language, Some("es".to_string()));
+        assert_eq!(chat.units, None);
+
+        db_controller
+            .modify_units(BOT_ID, &555555, 5555555, "imperial")
+            .await
+            .unwrap();
+
+        let chat = db_controller
+            .get_chat(BOT_ID, &555555, 5555555)
+            .await
+            .unwrap();
+        assert_eq!(chat.units, Some("imperial".to_string()));
+
+        let other_chat = db_controller.get_chat(2, &555555, 5555555).await.unwrap();
+        assert_eq!(other_chat.bot_id, 2);
+        assert_eq!(other_chat.offset, None);
+
+        db_controller
+            .delete_client(BOT_ID, &555555, 5555555)
+            .await
+            .unwrap();
+        db_controller
+            .delete_client(2, &555555, 5555555)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_live_messages() {
+        let db_controller = Repo::new().await.unwrap();
+        let connection = db_controller.connection().await.unwrap();
 
-        let pattern = format!("%{}%", pattern.to_uppercase());
+        let city = SeedCity {
+            name: "Test Live".to_string(),
+            state: "".to_string(),
+            country: "ZZ".to_string(),
+            coord: Coord::builder().lat(0.0).lon(0.0).build(),
+        };
+        db_controller.insert_city(city).await.unwrap();
+        let city = db_controller
+            .search_city("Test Live", "ZZ", "")
+            .await
+            .unwrap();
 
-        let vec = connection.query(GET_CITY_BY_PATTERN, &[&pattern]).await?;
-        Ok(vec)
+        let past = Utc::now() - chrono::Duration::minutes(1);
+
+        db_controller
+            .insert_live_message(BOT_ID, &-666666, 10, &city.id, "en", "metric", past)
+            .await
+            .unwrap();
+
+        // A new live message replaces the previous one of the chat.
+        db_controller
+            .insert_live_message(BOT_ID, &-666666, 11, &city.id, "es", "imperial", past)
+            .await
+            .unwrap();
+
+        let live_messages = db_controller
+            .get_live_messages_by_time(BOT_ID)
+            .await
+            .unwrap();
+        assert!(live_messages
+            .iter()
+            .any(|live_message| live_message.chat_id == -666666
+                && live_message.message_id == 11
+                && live_message.language == "es"
+                && live_message.units == "imperial"));
+
+        let n = db_controller
+            .update_live_message(BOT_ID, &-666666, 10, Utc::now())
+            .await
+            .unwrap();
+        assert_eq!(n, 0);
+
+        let n = db_controller
+            .update_live_message(
+                BOT_ID,
+                &-666666,
+                11,
+                Utc::now() + chrono::Duration::minutes(15),
+            )
+            .await
+            .unwrap();
+        assert_eq!(n, 1);
+
+        let live_messages = db_controller
+            .get_live_messages_by_time(BOT_ID)
+            .await
+            .unwrap();
+        assert!(!live_messages
+            .iter()
+            .any(|live_message| live_message.chat_id == -666666));
+
+        let n = db_controller
+            .delete_live_message(BOT_ID, &-666666, 11)
+            .await
+            .unwrap();
+        assert_eq!(n, 1);
+
+        let live_message = db_controller
+            .get_live_message(BOT_ID, &-666666)
+            .await
+            .unwrap();
+        assert!(live_message.is_none());
+
+        connection
+            .execute("DELETE FROM cities WHERE id = $1", &[&city.id])
+            .await
+            .unwrap();
     }
 
-    pub async fn get_city_row(&self, city: &str, n: usize) -> Result<City, BotDbError> {
-        let vec: Vec<Row> = self.get_city_by_pattern(city).await?;
-        if n > vec.len() {
-            return Err(BotDbError::CityNotFoundError);
-        }
+    #[tokio::test]
+    async fn test_delete_forecast() {
+        let db_controller = Repo::new().await.unwrap();
+        let connection = db_controller.connection().await.unwrap();
+
+        let city = SeedCity {
+            name: "Test Delete Forecast".to_string(),
+            state: "".to_string(),
+            country: "ZZ".to_string(),
+            coord: Coord::builder().lat(0.0).lon(0.0).build(),
+        };
+        db_controller.insert_city(city).await.unwrap();
+        let city = db_controller
+            .search_city("Test Delete Forecast", "ZZ", "")
+            .await
+            .unwrap();
+
+        db_controller
+            .insert_client(BOT_ID, &777777, 7777777)
+            .await
+            .unwrap();
+        let forecast = db_controller
+            .insert_forecast(
+                BOT_ID,
+                &777777,
+                7777777,
+                &city.id,
+                "0 0 0 * * * *".to_string(),
+            )
+            .await
+            .unwrap();
+
+        let next_delivery_at = Repo::calculate_next_delivery("0 30 7 * * Sat,Sun *").unwrap();
+
+        let other = db_controller
+            .reschedule_forecast(
+                BOT_ID,
+                &777777,
+                8888888,
+                &forecast.id,
+                "0 30 7 * * Sat,Sun *".to_string(),
+                next_delivery_at,
+            )
+            .await
+            .unwrap();
+        assert!(other.is_none());
+
+        let edited = db_controller
+            .reschedule_forecast(
+                BOT_ID,
+                &777777,
+                7777777,
+                &forecast.id,
+                "0 30 7 * * Sat,Sun *".to_string(),
+                next_delivery_at,
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(edited.id, forecast.id);
+        assert_eq!(edited.cron_expression, "0 30 7 * * Sat,Sun *");
+        assert_eq!(edited.last_delivered_at, None);
+
+        let stored = db_controller
+            .get_forecast_by_id(BOT_ID, &777777, 7777777, &forecast.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.cron_expression, "0 30 7 * * Sat,Sun *");
+
+        // Scheduling the same city again replaces its schedule.
+        let scheduled = db_controller
+            .schedule_forecast(
+                BOT_ID,
+                &777777,
+                7777777,
+                &city.id,
+                "0 0 9 * * Mon-Fri *".to_string(),
+                next_delivery_at,
+            )
+            .await
+            .unwrap();
+        assert_eq!(scheduled.id, forecast.id);
+        assert_eq!(scheduled.cron_expression, "0 0 9 * * Mon-Fri *");
+
+        // Somebody else can not see or remove the forecast.
+        let other = db_controller
+            .get_forecast_by_id(BOT_ID, &777777, 8888888, &forecast.id)
+            .await
+            .unwrap();
+        assert!(other.is_none());
+
+        let other = db_controller
+            .delete_forecast(BOT_ID, &777777, 8888888, &forecast.id)
+            .await
+            .unwrap();
+        assert!(other.is_none());
+
+        let deleted = db_controller
+            .delete_forecast(BOT_ID, &777777, 7777777, &forecast.id)
+            .await
+            .unwrap();
+        assert_eq!(deleted.unwrap().city_id, city.id);
 
-        Ok(Self::record_to_city(&vec[n - 1]))
+        let forecasts = db_controller
+            .get_forecasts_by_user(BOT_ID, &777777, 7777777)
+            .await
+            .unwrap();
+        assert!(forecasts.is_empty());
+
+        db_controller
+            .delete_client(BOT_ID, &777777, 7777777)
+            .await
+            .unwrap();
+        connection
+            .execute("DELETE FROM cities WHERE id = $1", &[&city.id])
+            .await
+            .unwrap();
     }
 
-    pub fn record_to_city(record: &Row) -> City {
-        let coord = Coord::builder()
-            .lon(record.get("lon"))
-            .lat(record.get("lat"))
-            .build();
+    #[tokio::test]
+    async fn test_pause_and_resume_forecasts() {
+        let db_controller = Repo::new().await.unwrap();
+        let connection = db_controller.connection().await.unwrap();
 
-        City::builder()
-            .id(record.get("id"))
-            .name(record.get("name"))
-            .country(record.get("country"))
-            .state(record.get("state"))
-            .coord(coord)
-            .build()
+        let city = SeedCity {
+            name: "Test Pause".to_string(),
+            state: "".to_string(),
+            country: "ZZ".to_string(),
+            coord: Coord::builder().lat(0.0).lon(0.0).build(),
+        };
+        db_controller.insert_city(city).await.unwrap();
+        let city = db_controller
+            .search_city("Test Pause", "ZZ", "")
+            .await
+            .unwrap();
+
+        db_controller
+            .insert_client(BOT_ID, &999999, 9999999)
+            .await
+            .unwrap();
+        let forecast = db_controller
+            .insert_forecast(
+                BOT_ID,
+                &999999,
+                9999999,
+                &city.id,
+                "0 0 0 * * * *".to_string(),
+            )
+            .await
+            .unwrap();
+
+        // A pause that already ended is resumed by the next check.
+        let past = Utc::now() - chrono::Duration::days(1);
+        let paused = db_controller
+            .pause_forecasts(BOT_ID, &999999, 9999999, Some(past))
+            .await
+            .unwrap();
+        assert_eq!(paused.len(), 1);
+        assert!(paused[0].paused);
+
+        let resumed = db_controller.resume_expired_pauses(BOT_ID).await.unwrap();
+        assert!(resumed.iter().any(|resumed| resumed.id == forecast.id));
+
+        let forecast = db_controller
+            .get_forecast(BOT_ID, &999999, 9999999, &city.id)
+            .await
+            .unwrap();
+        assert!(!forecast.paused);
+        assert_eq!(forecast.paused_until, None);
+        assert!(forecast.next_delivery_at > Utc::now());
+
+        db_controller
+            .pause_forecasts(BOT_ID, &999999, 9999999, None)
+            .await
+            .unwrap();
+
+        let resumed = db_controller.resume_expired_pauses(BOT_ID).await.unwrap();
+        assert!(!resumed.iter().any(|resumed| resumed.id == forecast.id));
+
+        let resumed = db_controller
+            .resume_forecasts(BOT_ID, &999999, 9999999)
+            .await
+            .unwrap();
+        assert_eq!(resumed.len(), 1);
+
+        db_controller
+            .delete_forecasts(BOT_ID, &999999, 9999999)
+            .await
+            .unwrap();
+        db_controller
+            .delete_client(BOT_ID, &999999, 9999999)
+            .await
+            .unwrap();
+        connection
+            .execute("DELETE FROM cities WHERE id = $1", &[&city.id])
+            .await
+            .unwrap();
     }
-}
-#[cfg(test)]
-mod db_test {
-    use crate::db::*;
-    use bb8_postgres::tokio_postgres::Row;
 
     #[tokio::test]
-    async fn test_modify_state() {
-        // Pick a random user of the DB
+    async fn test_favorites() {
         let db_controller = Repo::new().await.unwrap();
-        let connection = db_controller.pool.get().await.unwrap();
+        let connection = db_controller.connection().await.unwrap();
 
-        let n = db_controller.insert_client(&111111, 1111111).await.unwrap();
+        let city = SeedCity {
+            name: "Test Favorite".to_string(),
+            state: "".to_string(),
+            country: "ZZ".to_string(),
+            coord: Coord::builder().lat(0.0).lon(0.0).build(),
+        };
+        db_controller.insert_city(city).await.unwrap();
+        let city = db_controller
+            .search_city("Test Favorite", "ZZ", "")
+            .await
+            .unwrap();
 
-        assert_eq!(n, 1_u64);
+        db_controller
+            .insert_client(BOT_ID, &-121212, 1212121)
+            .await
+            .unwrap();
 
-        let row: &Row = &connection
-            .query_one("SELECT * FROM chats LIMIT 1", &[])
+        let favorite = db_controller
+            .insert_favorite(BOT_ID, &-121212, 1212121, &city.id)
             .await
+            .unwrap()
             .unwrap();
+        assert_eq!(favorite.user_id, 1212121);
+        assert_eq!(favorite.name, None);
 
-        let chat_id: i64 = row.get("id");
+        // The same city is only kept once.
+        let again = db_controller
+            .insert_favorite(BOT_ID, &-121212, 1212121, &city.id)
+            .await
+            .unwrap();
+        assert!(again.is_none());
 
-        let bytes: &[u8] = row.get("user_id");
-        let mut arr = [0u8; 8];
-        arr.copy_from_slice(bytes);
-        let user_id = Repo::as_u64_le(&arr);
+        let renamed = db_controller
+            .rename_favorite(BOT_ID, &-121212, 1212121, &favorite.id, "Home")
+            .await
+            .unwrap();
+        assert_eq!(renamed.unwrap().name, Some("Home".to_string()));
 
-        // testing modify state
+        // Somebody else can not remove the favorite.
+        let other = db_controller
+            .delete_favorite(BOT_ID, &-121212, 3434343, &favorite.id)
+            .await
+            .unwrap();
+        assert!(other.is_none());
 
-        let n = db_controller
-            .modify_state(&chat_id, user_id, ClientState::FindCity)
+        let favorites = db_controller
+            .get_favorites(BOT_ID, &-121212, 1212121)
             .await
             .unwrap();
+        assert_eq!(favorites.len(), 1);
 
-        assert_eq!(n, 1_u64);
+        // Favorites go away with the chat.
+        db_controller
+            .delete_client(BOT_ID, &-121212, 1212121)
+            .await
+            .unwrap();
 
-        // testing get state
-        let chat = db_controller.get_chat(&chat_id, user_id).await.unwrap();
+        let favorites = db_controller
+            .get_favorites(BOT_ID, &-121212, 1212121)
+            .await
+            .unwrap();
+        assert!(favorites.is_empty());
 
-        assert_eq!(chat.state, ClientState::FindCity);
+        connection
+            .execute("DELETE FROM cities WHERE id = $1", &[&city.id])
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_nearest_city_across_the_antimeridian() {
+        let db_controller = Repo::new().await.unwrap();
+        let connection = db_controller.connection().await.unwrap();
+
+        for (name, lon) in [("Test East", 179.9), ("Test West", -175.0)] {
+            let city = SeedCity {
+                name: name.to_string(),
+                state: "".to_string(),
+                country: "ZZ".to_string(),
+                coord: Coord::builder().lat(65.0).lon(lon).build(),
+            };
+            db_controller.insert_city(city).await.unwrap();
+        }
+
+        let coord = Coord::builder().lat(65.0).lon(-179.9).build();
+        let city = db_controller.get_nearest_city(&coord).await.unwrap();
+        assert_eq!(city.name, "Test East");
+
+        // No city in the box around the location, the whole table is searched.
+        let coord = Coord::builder().lat(80.0).lon(-177.0).build();
+        let city = db_controller.get_nearest_city(&coord).await.unwrap();
+        assert_eq!(city.name, "Test West");
+
+        connection
+            .execute(
+                "DELETE FROM cities WHERE name IN ('Test East', 'Test West')",
+                &[],
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_requeue_interrupted_tasks() {
+        let db_controller = Repo::new().await.unwrap();
+        let connection = db_controller.connection().await.unwrap();
+
+        for worker_id in ["requeue_test_own", "requeue_test_other"] {
+            connection
+                .execute(
+                    "INSERT INTO fang_tasks (metadata, task_type, state, worker_id) VALUES ('{}', 'requeue_test', 'in_progress', $1)",
+                    &[&worker_id],
+                )
+                .await
+                .unwrap();
+        }
 
         let n = db_controller
-            .modify_state(&chat_id, user_id, ClientState::Initial)
+            .requeue_interrupted_tasks("requeue_test_own")
             .await
             .unwrap();
-        assert_eq!(n, 1_u64);
+        assert_eq!(n, 1);
 
-        let chat = db_controller.get_chat(&chat_id, user_id).await.unwrap();
+        let row = connection
+            .query_one(
+                "SELECT state::text FROM fang_tasks WHERE worker_id = 'requeue_test_other'",
+                &[],
+            )
+            .await
+            .unwrap();
+        let state: String = row.get(0);
+        assert_eq!(state, "in_progress");
 
-        assert_eq!(chat.state, ClientState::Initial);
+        connection
+            .execute(
+                "DELETE FROM fang_tasks WHERE task_type = 'requeue_test'",
+                &[],
+            )
+            .await
+            .unwrap();
+    }
 
-        let n = db_controller.delete_client(&111111, 1111111).await.unwrap();
-        assert_eq!(n, 1_u64);
+    #[tokio::test]
+    async fn test_fetch_task_keeps_the_order_of_a_chat() {
+        let db_controller = Repo::new().await.unwrap();
+        let connection = db_controller.connection().await.unwrap();
+
+        let insert = "INSERT INTO fang_tasks (metadata, task_type, state, scheduled_at, created_at) VALUES ($1, 'fetch_test', $2::text::fang_task_state, $3, $4)";
+        let now = Utc::now();
+        let retry_at = now + chrono::Duration::minutes(1);
+        let created_at = now - chrono::Duration::minutes(1);
+
+        // The first update of chat 1 failed and waits for its retry.
+        for (chat_id, state, scheduled_at, created_at) in [
+            (1, "retried", retry_at, created_at),
+            (1, "new", now, now),
+            (2, "new", now, now),
+        ] {
+            let metadata = serde_json::json!({ "chat_id": chat_id });
+
+            connection
+                .execute(insert, &[&metadata, &state, &scheduled_at, &created_at])
+                .await
+                .unwrap();
+        }
+
+        let task = db_controller
+            .fetch_task("fetch_test", true, "test_worker")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(task.metadata["chat_id"], 2);
+
+        assert!(db_controller
+            .fetch_task("fetch_test", true, "test_worker")
+            .await
+            .unwrap()
+            .is_none());
+
+        // Without the order the later update of chat 1 runs.
+        let task = db_controller
+            .fetch_task("fetch_test", false, "test_worker")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(task.metadata["chat_id"], 1);
+
+        connection
+            .execute("DELETE FROM fang_tasks WHERE task_type = 'fetch_test'", &[])
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_put_back_task() {
+        let db_controller = Repo::new().await.unwrap();
+        let connection = db_controller.connection().await.unwrap();
+
+        connection
+            .execute(
+                "INSERT INTO fang_tasks (metadata, task_type, retries) VALUES ('{}', 'put_back_test', 2)",
+                &[],
+            )
+            .await
+            .unwrap();
+
+        let task = db_controller
+            .fetch_task("put_back_test", false, "test_worker")
+            .await
+            .unwrap()
+            .unwrap();
+
+        let n = db_controller.put_back_task(&task).await.unwrap();
+        assert_eq!(n, 1);
+
+        let task = db_controller
+            .fetch_task("put_back_test", false, "test_worker")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(task.retries, 2);
+
+        connection
+            .execute(
+                "DELETE FROM fang_tasks WHERE task_type = 'put_back_test'",
+                &[],
+            )
+            .await
+            .unwrap();
     }
 }
@@ -1,6 +1,16 @@
+use crate::db::LiveMessage;
 use crate::db::Repo;
+use crate::locale::Language;
 use crate::open_weather_map::client::WeatherApiClient;
+use crate::open_weather_map::units::Units;
+use crate::open_weather_map::weather::Weather;
+use crate::open_weather_map::City;
+use crate::shutdown;
 use crate::telegram::client::ApiClient;
+use crate::telegram::client::ApiError;
+use crate::telegram::client::TextProgress;
+use crate::LIVE_REFRESH_MINUTES;
+use chrono::Duration;
 use fang::async_trait;
 use fang::typetag;
 use fang::AsyncQueueable;
@@ -18,9 +28,14 @@ pub const SCHEDULED_TASK_TYPE: &str = "scheduled_forecast";
 // Every 30 seconds this DeliverChecker is executed.
 pub const CRON_DELIVER_CHECKER: &str = "0/30 * * * * * *";
 
+// Every minute this LiveMessageRefresher is executed.
+pub const CRON_LIVE_MESSAGE_REFRESHER: &str = "0 * * * * * *";
+
 #[derive(Serialize, Deserialize, Debug, TypedBuilder, Eq, PartialEq, Clone)]
 #[serde(crate = "fang::serde")]
 pub struct ScheduleWeatherTask {
+    #[serde(default)]
+    bot_id: i64,
     cron_expression: String,
     chat_id: i64,
     user_id: u64,
@@ -40,11 +55,13 @@ impl ScheduleWeatherTask {
 #[typetag::serde]
 #[async_trait]
 impl AsyncRunnable for ScheduleWeatherTask {
-    async fn run(&self, _queueable: &mut dyn AsyncQueueable) -> Result<(), FangError> {
+    async fn run(&self, queueable: &mut dyn AsyncQueueable) -> Result<(), FangError> {
+        let _running_task = shutdown::start_task()?;
+
         // Here we should do one deliver.
         let repo = Repo::repo().await?;
 
-        let api = ApiClient::api_client().await;
+        let api = ApiClient::api_client(self.bot_id).await?;
 
         let city = repo.search_city_by_id(&self.city_id).await?;
 
@@ -52,6 +69,7 @@ impl AsyncRunnable for ScheduleWeatherTask {
         // Insert forecast in forecasts table if not exists or update the forecasts table.
 
         repo.update_or_insert_forecast(
+            api.bot_id(),
             &self.chat_id,
             self.user_id,
             &self.city_id,
@@ -60,20 +78,38 @@ impl AsyncRunnable for ScheduleWeatherTask {
         )
         .await?;
 
+        let chat = repo
+            .get_chat(api.bot_id(), &self.chat_id, self.user_id)
+            .await?;
+        let language = chat
+            .language
+            .as_deref()
+            .and_then(Language::from_code)
+            .unwrap_or_default();
+        let units = chat
+            .units
+            .as_deref()
+            .and_then(Units::from_code)
+            .unwrap_or_default();
+
         let weather_client = WeatherApiClient::weather_client().await;
 
         let weather_info = weather_client
-            .fetch_weekly(city.coord.lat, city.coord.lon)
+            .fetch_weekly(city.coord.lat, city.coord.lon, language, units)
             .await?;
 
-        let text = format!(
-            "Here is your forecast !, this is your scheduled weather info.\n\n {},{}\nLat {} , Lon {}\n{}",
-            city.name, city.country, city.coord.lat, city.coord.lon, weather_info,
+        let text = language.format(
+            "scheduled_weather",
+            &[
+                &city.name,
+                &city.country,
+                &city.coord.lat,
+                &city.coord.lon,
+                &weather_info,
+            ],
         );
 
-        api.send_message_without_reply(self.chat_id, text).await?;
-
-        Ok(())
+        deliver_message(queueable, api, self.chat_id, text, TextProgress::default()).await
     }
 
     fn uniq(&self) -> bool {
@@ -85,6 +121,118 @@ impl AsyncRunnable for ScheduleWeatherTask {
     }
 }
 
+// Rate limited messages are sent again later and chats that blocked the bot
+// are deactivated, neither of them fails the delivery task.
+async fn deliver_message(
+    queueable: &mut dyn AsyncQueueable,
+    api: &ApiClient,
+    chat_id: i64,
+    text: String,
+    progress: TextProgress,
+) -> Result<(), FangError> {
+    match api.send_message_from(chat_id, text.clone(), progress).await {
+        Ok(_) => Ok(()),
+
+        // The parts of the text which were sent are not sent again.
+        Err(ApiError::TooManyRequests(retry_after, progress)) => {
+            SendMessageTask::requeue(
+                queueable,
+                api.bot_id(),
+                chat_id,
+                text,
+                retry_after,
+                progress,
+            )
+            .await
+        }
+
+        Err(ApiError::ChatUnreachable(description)) => {
+            log::info!(
+                "Chat {} can not receive messages ({}), suspending its forecasts",
+                chat_id,
+                description
+            );
+
+            let repo = Repo::repo().await?;
+
+            repo.deactivate_chat(api.bot_id(), &chat_id).await?;
+
+            Ok(())
+        }
+
+        Err(err) => Err(err.into()),
+    }
+}
+
+// A message Telegram rejected with a retry_after, sent again once that period is over.
+#[derive(Serialize, Deserialize, Debug, TypedBuilder, Eq, PartialEq, Clone)]
+#[serde(crate = "fang::serde")]
+pub struct SendMessageTask {
+    #[serde(default)]
+    bot_id: i64,
+    chat_id: i64,
+    text: String,
+    #[serde(default)]
+    progress: TextProgress,
+    scheduled_at: DateTime<Utc>,
+}
+
+impl SendMessageTask {
+    pub async fn requeue(
+        queueable: &mut dyn AsyncQueueable,
+        bot_id: i64,
+        chat_id: i64,
+        text: String,
+        retry_after: u16,
+        progress: TextProgress,
+    ) -> Result<(), FangError> {
+        log::info!(
+            "Telegram asked to retry after {} seconds, requeueing message to chat {}",
+            retry_after,
+            chat_id
+        );
+
+        let task = Self::builder()
+            .bot_id(bot_id)
+            .chat_id(chat_id)
+            .text(text)
+            .progress(progress)
+            .scheduled_at(Utc::now() + Duration::seconds(retry_after.into()))
+            .build();
+
+        queueable.schedule_task(&task).await?;
+
+        Ok(())
+    }
+}
+
+#[typetag::serde]
+#[async_trait]
+impl AsyncRunnable for SendMessageTask {
+    async fn run(&self, queueable: &mut dyn AsyncQueueable) -> Result<(), FangError> {
+        let _running_task = shutdown::start_task()?;
+
+        let api = ApiClient::api_client(self.bot_id).await?;
+
+        deliver_message(
+            queueable,
+            api,
+            self.chat_id,
+            self.text.clone(),
+            self.progress,
+        )
+        .await
+    }
+
+    fn task_type(&self) -> String {
+        SCHEDULED_TASK_TYPE.to_string()
+    }
+
+    fn cron(&self) -> Option<Scheduled> {
+        Some(Scheduled::ScheduleOnce(self.scheduled_at))
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 #[serde(crate = "fang::serde")]
 pub struct DeliverChecker;
@@ -96,21 +244,37 @@ impl AsyncRunnable for DeliverChecker {
         // Here we should fetch every forecast from forecasts table that
         // next_delivery_at <= Utc::now()
 
+        // Forecasts due now are picked up by the first run after the restart.
+        let _running_task = match shutdown::start_task() {
+            Ok(running_task) => running_task,
+            Err(_) => return Ok(()),
+        };
+
         log::info!("DeliverChecker run");
 
         let repo = Repo::repo().await?;
 
-        let forecasts = repo.get_forecasts_by_time().await?;
+        // Forecasts of bots that are not running in this process stay untouched.
+        for api in ApiClient::api_clients().await?.iter() {
+            let resumed = repo.resume_expired_pauses(api.bot_id()).await?;
+
+            if !resumed.is_empty() {
+                log::info!("{} paused forecasts resumed", resumed.len());
+            }
 
-        for forecast in forecasts.into_iter() {
-            let task = ScheduleWeatherTask::builder()
-                .cron_expression(forecast.cron_expression)
-                .chat_id(forecast.chat_id)
-                .user_id(forecast.user_id)
-                .city_id(forecast.city_id)
-                .build();
+            let forecasts = repo.get_forecasts_by_time(api.bot_id()).await?;
 
-            queueable.insert_task(&task).await?;
+            for forecast in forecasts.into_iter() {
+                let task = ScheduleWeatherTask::builder()
+                    .bot_id(forecast.bot_id)
+                    .cron_expression(forecast.cron_expression)
+                    .chat_id(forecast.chat_id)
+                    .user_id(forecast.user_id)
+                    .city_id(forecast.city_id)
+                    .build();
+
+                queueable.insert_task(&task).await?;
+            }
         }
 
         Ok(())
@@ -128,3 +292,179 @@ impl AsyncRunnable for DeliverChecker {
         Some(Scheduled::CronPattern(CRON_DELIVER_CHECKER.to_string()))
     }
 }
+
+pub fn live_message_text(city: &City, weather: &Weather, language: Language) -> String {
+    language.format(
+        "live_weather",
+        &[
+            &Utc::now().format("%H:%M"),
+            &city.name,
+            &city.country,
+            &city.coord.lat,
+            &city.coord.lon,
+            weather,
+        ],
+    )
+}
+
+pub fn next_live_refresh() -> DateTime<Utc> {
+    Utc::now() + Duration::minutes(*LIVE_REFRESH_MINUTES)
+}
+
+#[derive(Serialize, Deserialize, Debug, TypedBuilder, Eq, PartialEq, Clone)]
+#[serde(crate = "fang::serde")]
+pub struct RefreshLiveMessageTask {
+    bot_id: i64,
+    chat_id: i64,
+    message_id: i32,
+    city_id: i32,
+    #[serde(default)]
+    language: String,
+    #[serde(default)]
+    units: String,
+}
+
+impl RefreshLiveMessageTask {
+    async fn stop(&self, repo: &Repo, reason: &str) -> Result<(), FangError> {
+        log::info!(
+            "Live message {} of chat {} {}, it will not be refreshed anymore",
+            self.message_id,
+            self.chat_id,
+            reason
+        );
+
+        repo.delete_live_message(self.bot_id, &self.chat_id, self.message_id)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[typetag::serde]
+#[async_trait]
+impl AsyncRunnable for RefreshLiveMessageTask {
+    async fn run(&self, _queueable: &mut dyn AsyncQueueable) -> Result<(), FangError> {
+        // A refresh missed during a shutdown is done by the next LiveMessageRefresher run.
+        let _running_task = match shutdown::start_task() {
+            Ok(running_task) => running_task,
+            Err(_) => return Ok(()),
+        };
+
+        let repo = Repo::repo().await?;
+
+        let api = ApiClient::api_client(self.bot_id).await?;
+
+        // Pinning another message stops the live message when the update arrives,
+        // here only a chat without any pinned message is known to have unpinned it.
+        match api.pinned_message_id(self.chat_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => return self.stop(repo, "was unpinned").await,
+            Err(ApiError::ChatUnreachable(_)) => return self.stop(repo, "is unreachable").await,
+            Err(err) => return Err(err.into()),
+        }
+
+        let n = repo
+            .update_live_message(
+                self.bot_id,
+                &self.chat_id,
+                self.message_id,
+                next_live_refresh(),
+            )
+            .await?;
+
+        // It was replaced by a newer live message or stopped meanwhile.
+        if n == 0 {
+            return Ok(());
+        }
+
+        let city = repo.search_city_by_id(&self.city_id).await?;
+
+        let weather_client = WeatherApiClient::weather_client().await;
+
+        let language = Language::from_code(&self.language).unwrap_or_default();
+        let units = Units::from_code(&self.units).unwrap_or_default();
+
+        let weather_info = weather_client
+            .fetch(city.coord.lat, city.coord.lon, language, units)
+            .await?;
+
+        let text = live_message_text(&city, &weather_info, language);
+
+        match api
+            .edit_message_text(self.chat_id, self.message_id, text)
+            .await
+        {
+            Ok(()) => Ok(()),
+            Err(ApiError::MessageUnavailable(_)) | Err(ApiError::ChatUnreachable(_)) => {
+                self.stop(repo, "can not be edited").await
+            }
+            // The message is edited again on the next refresh.
+            Err(ApiError::TooManyRequests(..)) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn uniq(&self) -> bool {
+        true
+    }
+
+    fn task_type(&self) -> String {
+        SCHEDULED_TASK_TYPE.to_string()
+    }
+}
+
+impl From<LiveMessage> for RefreshLiveMessageTask {
+    fn from(live_message: LiveMessage) -> Self {
+        Self::builder()
+            .bot_id(live_message.bot_id)
+            .chat_id(live_message.chat_id)
+            .message_id(live_message.message_id)
+            .city_id(live_message.city_id)
+            .language(live_message.language)
+            .units(live_message.units)
+            .build()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+#[serde(crate = "fang::serde")]
+pub struct LiveMessageRefresher;
+
+#[typetag::serde]
+#[async_trait]
+impl AsyncRunnable for LiveMessageRefresher {
+    async fn run(&self, queueable: &mut dyn AsyncQueueable) -> Result<(), FangError> {
+        let _running_task = match shutdown::start_task() {
+            Ok(running_task) => running_task,
+            Err(_) => return Ok(()),
+        };
+
+        let repo = Repo::repo().await?;
+
+        for api in ApiClient::api_clients().await?.iter() {
+            let live_messages = repo.get_live_messages_by_time(api.bot_id()).await?;
+
+            for live_message in live_messages.into_iter() {
+                let task = RefreshLiveMessageTask::from(live_message);
+
+                queueable.insert_task(&task).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn uniq(&self) -> bool {
+        true
+    }
+
+    fn task_type(&self) -> String {
+        SCHEDULED_TASK_TYPE.to_string()
+    }
+
+    fn cron(&self) -> Option<Scheduled> {
+        Some(Scheduled::CronPattern(
+            CRON_LIVE_MESSAGE_REFRESHER.to_string(),
+        ))
+    }
+}
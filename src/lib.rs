@@ -1,24 +1,65 @@
 pub mod command;
 pub mod db;
 pub mod deliver;
+pub mod locale;
 pub mod open_weather_map;
 pub mod seeds;
+pub mod shutdown;
 pub mod telegram;
 pub mod workers;
 
 use crate::db::BotDbError;
 use crate::open_weather_map::client::ClientError;
 use crate::telegram::client::ApiError;
+use fang::FangError;
 use lazy_static::lazy_static;
+use std::time::Duration;
 use thiserror::Error;
 
 lazy_static! {
     pub static ref RUST_TELEGRAM_BOT_TOKEN: String =
         std::env::var("RUST_TELEGRAM_BOT_TOKEN").expect("RUST_TELEGRAM_BOT_TOKEN not set");
+    // Several bots run from one process with their tokens separated by commas,
+    // the first one is the primary bot.
+    pub static ref BOT_TOKENS: Vec<String> = {
+        let tokens: Vec<String> = RUST_TELEGRAM_BOT_TOKEN
+            .split(',')
+            .map(|token| token.trim().to_string())
+            .filter(|token| !token.is_empty())
+            .collect();
+
+        assert!(!tokens.is_empty(), "RUST_TELEGRAM_BOT_TOKEN is empty");
+
+        tokens
+    };
     pub static ref OPEN_WEATHER_MAP_API_TOKEN: String =
         std::env::var("OPEN_WEATHER_MAP_API_TOKEN").expect("OPEN_WEATHER_MAP_API_TOKEN not set");
     pub static ref DATABASE_URL: String =
         std::env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    pub static ref BOT_MODE: String =
+        std::env::var("BOT_MODE").unwrap_or_else(|_| "polling".to_string());
+    pub static ref WEBHOOK_URL: String = std::env::var("WEBHOOK_URL").expect("WEBHOOK_URL not set");
+    pub static ref WEBHOOK_SECRET_TOKEN: String =
+        std::env::var("WEBHOOK_SECRET_TOKEN").expect("WEBHOOK_SECRET_TOKEN not set");
+    pub static ref WEBHOOK_ADDRESS: String =
+        std::env::var("WEBHOOK_ADDRESS").unwrap_or_else(|_| "0.0.0.0:8443".to_string());
+    // Minutes between two edits of a live weather message.
+    pub static ref LIVE_REFRESH_MINUTES: i64 = std::env::var("LIVE_REFRESH_MINUTES")
+        .ok()
+        .and_then(|minutes| minutes.parse().ok())
+        .unwrap_or(15);
+    // Name of this instance of the bot, the tasks it runs are marked with it so
+    // the next start of the instance requeues the ones it left unfinished.
+    // Instances sharing a database need different names.
+    pub static ref WORKER_ID: String =
+        std::env::var("WORKER_ID").unwrap_or_else(|_| "weather_bot".to_string());
+    // Docker kills the container 10 seconds after SIGTERM by default.
+    pub static ref SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(
+        std::env::var("SHUTDOWN_TIMEOUT")
+            .ok()
+            .and_then(|seconds| seconds.parse().ok())
+            .unwrap_or(8)
+    );
 }
 
 #[derive(Debug, Error)]
@@ -33,4 +74,15 @@ pub enum BotError {
     DbError(#[from] BotDbError),
     #[error(transparent)]
     WeatherApiError(#[from] ClientError),
+    #[error(transparent)]
+    WebhookServerError(#[from] hyper::Error),
+    #[error(transparent)]
+    AddressError(#[from] std::net::AddrParseError),
+}
+
+impl From<BotError> for FangError {
+    fn from(error: BotError) -> Self {
+        let description = format!("{:?}", error);
+        FangError { description }
+    }
 }
@@ -0,0 +1,129 @@
+mod en;
+mod es;
+
+use std::fmt;
+
+// The position of every `{}` of a text is taken by the next argument.
+const PLACEHOLDER: &str = "{}";
+
+/// Language of the bot texts, also sent to OpenWeatherMap to translate the
+/// weather descriptions.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Spanish];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Spanish => "es",
+        }
+    }
+
+    /// Name of the language in the language itself.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Spanish => "Español",
+        }
+    }
+
+    /// Accepts the codes Telegram reports like `es` or `es-ES`, and the names.
+    pub fn from_code(code: &str) -> Option<Self> {
+        let code = code.trim().to_lowercase();
+        let code = code.split(['-', '_']).next().unwrap_or_default();
+
+        Self::ALL
+            .into_iter()
+            .find(|language| language.code() == code || language.name().to_lowercase() == code)
+    }
+
+    fn messages(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Language::English => en::MESSAGES,
+            Language::Spanish => es::MESSAGES,
+        }
+    }
+
+    /// Text of the key in this language, in English when it is not translated.
+    pub fn text(&self, key: &str) -> &'static str {
+        let find = |messages: &'static [(&'static str, &'static str)]| {
+            messages
+                .iter()
+                .find(|(message_key, _)| *message_key == key)
+                .map(|(_, text)| *text)
+        };
+
+        find(self.messages())
+            .or_else(|| find(en::MESSAGES))
+            .unwrap_or_else(|| {
+                log::error!("Missing text {}", key);
+                ""
+            })
+    }
+
+    pub fn format(&self, key: &str, args: &[&dyn fmt::Display]) -> String {
+        let mut parts = self.text(key).split(PLACEHOLDER);
+        let mut text = parts.next().unwrap_or_default().to_string();
+
+        for (index, part) in parts.enumerate() {
+            if let Some(arg) = args.get(index) {
+                text.push_str(&arg.to_string());
+            }
+
+            text.push_str(part);
+        }
+
+        text
+    }
+}
+
+#[cfg(test)]
+mod locale_test {
+    use super::*;
+
+    #[test]
+    fn test_from_code() {
+        assert_eq!(Language::from_code("es-ES"), Some(Language::Spanish));
+        assert_eq!(Language::from_code("EN"), Some(Language::English));
+        assert_eq!(Language::from_code("español"), Some(Language::Spanish));
+        assert_eq!(Language::from_code("de"), None);
+    }
+
+    #[test]
+    fn test_format_falls_back_to_english() {
+        assert_eq!(
+            Language::Spanish.format("greeting", &[&"Ana", &"Hola"]),
+            "¡Hola, Ana!\nHola"
+        );
+        // The coordinates are written the same in every language.
+        assert_eq!(
+            Language::Spanish.text("city_weather"),
+            Language::English.text("city_weather")
+        );
+        assert_eq!(Language::English.text("missing"), "");
+    }
+
+    #[test]
+    fn test_catalogs_have_the_same_placeholders() {
+        for language in Language::ALL {
+            for (key, text) in language.messages().iter() {
+                let english = Language::English.text(key);
+
+                assert!(!english.is_empty(), "{} is not in English", key);
+                assert_eq!(
+                    text.matches(PLACEHOLDER).count(),
+                    english.matches(PLACEHOLDER).count(),
+                    "{} of {} has other placeholders",
+                    key,
+                    language.code()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,313 @@
+// English texts, the other catalogs fall back to them for a missing key.
+pub static MESSAGES: &[(&str, &str)] = &[
+    ("greeting", "Hi, {}!\n{}"),
+    ("city_weather", "{},{}\nLat {} , Lon {}\n{}"),
+    // Help
+    (
+        "help_header",
+        "This bot provides weather info around the globe.\nIn order to use it put the command:\n\n",
+    ),
+    (
+        "help_footer",
+        "\nIt would be really greatful if you take a look at my GitHub, look how much work I invested into this bot.\n\
+         If you like this bot, consider giving me a star on GitHub or if you would like to self run it, fork the project please.\n\
+         <a href=\"https://github.com/pxp9/weather_bot_rust\">RustWeatherBot GitHub repo</a>",
+    ),
+    // Weather
+    (
+        "weather",
+        "\n🌍🌍 Weather: {}\n🌡️🌡️ Mean Temperature: {}\n🧊🧊 Minimum temperature: {}\n🔥🔥 Maximum temperature: {}\n⛰️⛰️ Pressure: {} hPa\n💧💧 Humidity: {} %\n💨💨 Wind: {}",
+    ),
+    (
+        "forecast",
+        "\n==== {} ====\n🌍🌍 Weather: {}\n🌡️🌡️ Mean Temperature: {}\n🧊🧊 Minimum temperature: {}\n🔥🔥 Maximum temperature: {}\n⛰️⛰️ Pressure: {} hPa\n💧💧 Humidity: {} %\n💨💨 Wind: {}\n Rain probability: {} %",
+    ),
+    ("location_weather", "Your location, near {}\nLat {} , Lon {}\n{}"),
+    (
+        "scheduled_weather",
+        "Here is your forecast !, this is your scheduled weather info.\n\n {},{}\nLat {} , Lon {}\n{}",
+    ),
+    (
+        "live_weather",
+        "Live weather, updated at {} UTC\n\n{},{}\nLat {} , Lon {}\n{}",
+    ),
+    // Delivery days
+    ("monday", "Monday"),
+    ("tuesday", "Tuesday"),
+    ("wednesday", "Wednesday"),
+    ("thursday", "Thursday"),
+    ("friday", "Friday"),
+    ("saturday", "Saturday"),
+    ("sunday", "Sunday"),
+    ("every_day", "every day"),
+    ("on_weekdays", "on weekdays"),
+    ("on_weekends", "on weekends"),
+    ("every_weekday", "every {}"),
+    ("on_days", "on {} and {}"),
+    ("no_days", "never"),
+    ("schedule_at", "{} at {}"),
+    // Cities
+    (
+        "write_city",
+        "Write a city, let me see if I can find it. You can also share a location",
+    ),
+    (
+        "city_not_found_write_again",
+        "Your city {} was not found. Write a city, let me see if I can find it. You can also share a location",
+    ),
+    (
+        "city_not_found_cancelled",
+        "Your city {} was not found. Command cancelled.",
+    ),
+    ("select_city", "I found these cities. Select one"),
+    (
+        "select_city_from_list",
+        "Please select one of the cities from the list. The command was cancelled",
+    ),
+    (
+        "unknown_link_city",
+        "The city of this link was not found. See /start for available commands",
+    ),
+    // Default city and offset
+    ("default_city_is", "Your default city is {}"),
+    ("no_default_city", "You do not have default city"),
+    ("setting_default_city", "Setting default city..."),
+    ("city_updated.your", "Your default city was updated"),
+    ("city_updated.group", "The group default city was updated"),
+    ("city_updated.channel", "The channel default city was updated"),
+    ("offset_is", "Your offset is UTC {}"),
+    (
+        "no_offset",
+        "You do not have offset set. Please execute /set_offset",
+    ),
+    (
+        "offset_question",
+        "Do you have any offset respect UTC ?\n(0 if your timezone is the same as UTC, 2 if UTC + 2 , -2 if UTC - 2, [-11,12])",
+    ),
+    (
+        "invalid_offset",
+        "That's not a valid offset, it has to be a number in range [-11, 12].\n\
+         If your timezone is UTC + 2 put 2, if you have UTC - 10 put -10, 0 if you have UTC timezone.\n\
+         The command was cancelled",
+    ),
+    ("offset_set.your", "Your offset was set to {}"),
+    ("offset_set.group", "The group offset was set to {}"),
+    ("offset_set.channel", "The channel offset was set to {}"),
+    // Scheduled forecasts
+    (
+        "schedule_city_question",
+        "What city would you like to schedule ? You can also share a location",
+    ),
+    (
+        "schedule_time_question",
+        "What time would you like to schedule ? (format hour:minutes in range 0-23:0-59)\n\
+         Every day by default, add the days like `07:30 weekdays`, `Sat,Sun at 09:00` or `07:30 every Monday`",
+    ),
+    (
+        "invalid_time",
+        "That's not a well formatted time, it has to be formatted with this format `hour:minutes` being hour a number in range [0,23] \
+         and minutes a number in range [0,59], optionally with the days like `07:30 weekdays`. The command was cancelled",
+    ),
+    (
+        "schedule_without_offset",
+        "You can not schedule without offset set. Please execute /set_offset",
+    ),
+    ("forecast_scheduled", "Weather info scheduled {} UTC {}"),
+    ("forecasts_unscheduled.your", "Your forecasts were unscheduled"),
+    (
+        "forecasts_unscheduled.group",
+        "The group forecasts were unscheduled",
+    ),
+    (
+        "forecasts_unscheduled.channel",
+        "The channel forecasts were unscheduled",
+    ),
+    (
+        "no_scheduled_forecasts",
+        "There are no scheduled forecasts. Execute /schedule to add one",
+    ),
+    (
+        "scheduled_forecasts.your",
+        "Your scheduled forecasts, times in UTC {}\n",
+    ),
+    (
+        "scheduled_forecasts.group",
+        "The group scheduled forecasts, times in UTC {}\n",
+    ),
+    (
+        "scheduled_forecasts.channel",
+        "The channel scheduled forecasts, times in UTC {}\n",
+    ),
+    (
+        "scheduled_forecast",
+        "\n{}. {} {}\nNext delivery: {}\nLast delivery: {}\n",
+    ),
+    ("never_delivered", "never"),
+    ("paused", "paused"),
+    ("paused_until", "paused until {}"),
+    ("edit_button", "Edit {}"),
+    ("remove_button", "Remove {}"),
+    ("forecast_removed", "The forecast of {} was removed"),
+    (
+        "forecast_not_found",
+        "That forecast does not exist anymore. Execute /schedules to see them",
+    ),
+    (
+        "invalid_pause_date",
+        "That's not a valid date, it has to be a future date like /pause until 2026-08-31",
+    ),
+    (
+        "forecasts_paused_until",
+        "{} forecasts paused until {}. Execute /resume to resume them before",
+    ),
+    (
+        "forecasts_paused",
+        "{} forecasts paused. Execute /resume to resume them",
+    ),
+    ("no_paused_forecasts", "There are no paused forecasts"),
+    ("forecasts_resumed", "{} forecasts resumed"),
+    // Groups
+    (
+        "group_admins_only",
+        "Only the group administrators can change the group settings",
+    ),
+    (
+        "group_schedule_without_offset",
+        "The group can not schedule without offset set. Please execute /set_group_offset",
+    ),
+    (
+        "group_no_default_city",
+        "The group does not have default city. An administrator can set it with /set_group_city",
+    ),
+    ("only_groups", "This command only works in groups"),
+    // Live weather
+    (
+        "live_no_default_city",
+        "There is no default city. Please execute /set_default_city",
+    ),
+    (
+        "live_pin_failed",
+        "I could not pin the live message, I need permission to pin messages",
+    ),
+    (
+        "live_not_found",
+        "There is no live weather message in this chat",
+    ),
+    (
+        "live_stopped",
+        "The live weather message will not be updated anymore",
+    ),
+    // Favorites
+    ("favorite_cities.your", "Your favorite cities\n"),
+    ("favorite_cities.group", "The group favorite cities\n"),
+    ("favorite_cities.channel", "The channel favorite cities\n"),
+    ("favorite_weather", "\n⭐ {}: {}, {}"),
+    ("favorite_weather_unavailable", "\n⭐ {}: weather not available"),
+    (
+        "favorites_limit",
+        "You can have up to {} favorite cities. Execute /remove_favorite to make room",
+    ),
+    (
+        "add_favorite_question",
+        "What city would you like to add to your favorites ? You can also share a location",
+    ),
+    (
+        "favorite_added",
+        "{} was added to your favorites. Execute /favorites to see their weather",
+    ),
+    ("favorite_exists", "{} already is one of your favorites"),
+    ("favorite_removed", "{} was removed from your favorites"),
+    ("select_favorite_to_remove", "Select the favorite to remove"),
+    ("select_favorite_to_rename", "Select the favorite to rename"),
+    (
+        "favorite_name_question",
+        "How would you like to call {} ? (up to {} characters, like Home or Office)",
+    ),
+    (
+        "invalid_favorite_name",
+        "That's not a valid name, it has to have between 1 and {} characters. The command was cancelled",
+    ),
+    ("favorite_renamed", "Your favorite was renamed to {}"),
+    (
+        "no_favorites",
+        "You do not have favorite cities. Execute /add_favorite to add one",
+    ),
+    (
+        "favorite_not_found",
+        "That favorite does not exist anymore. Execute /favorites to see them",
+    ),
+    // Compare
+    ("compare_city", "City"),
+    ("compare_temp", "Temp"),
+    ("compare_humidity", "Humidity"),
+    ("compare_rain", "Rain 9h"),
+    ("compare_warmest", "🔥 Warmest: {} {}, {}"),
+    ("compare_driest", "🏜️ Driest: {} {} % humidity"),
+    ("compare_rainiest", "☔ Highest rain probability: {} {} %"),
+    ("compare_not_found", "{} was not found"),
+    (
+        "compare_ambiguous",
+        "{} matches several cities, add the country code after a comma",
+    ),
+    ("compare_truncated", "Only the first {} cities are compared"),
+    ("compare_unavailable", "The weather of {} is not available"),
+    (
+        "compare_usage",
+        "Write at least two cities to compare, like /compare Madrid, Lisbon, Paris, FR. Without cities your favorites are compared",
+    ),
+    // Language
+    ("language_question", "What language should I speak ?"),
+    ("language_set", "From now on I speak English"),
+    ("group_language_set", "The group forecasts are delivered in {} from now on"),
+    (
+        "unknown_language",
+        "I do not speak that language yet. Execute /language to see the available ones",
+    ),
+    // Units
+    ("units_question", "What units should I use ?"),
+    ("group_units_set", "The group forecasts are shown in {} from now on"),
+    ("units_set", "From now on the weather is shown in {}"),
+    (
+        "unknown_units",
+        "I do not know those units. Execute /units to see the available ones",
+    ),
+    // Other
+    ("operation_cancelled", "Your operation was canceled"),
+    (
+        "unknown_command",
+        "Unknown command. See /start for available commands",
+    ),
+    // Commands
+    ("command_start", "Show the available commands"),
+    ("command_find_city", "Ask weather info from any city worldwide"),
+    ("command_default", "Provides weather info from your default city"),
+    ("command_set_default_city", "Set your default city"),
+    ("command_current_default_city", "Show your default city"),
+    ("command_favorites", "Provides weather info from all your favorite cities"),
+    ("command_add_favorite", "Add a city to your favorites"),
+    ("command_remove_favorite", "Remove a city from your favorites"),
+    ("command_rename_favorite", "Give one of your favorites a name like Home or Office"),
+    ("command_compare", "Compare the weather of several cities, like /compare Madrid, Lisbon"),
+    ("command_schedule", "Schedule a forecast of a city every day, on weekdays or on chosen days"),
+    ("command_unschedule", "Remove all your scheduled forecasts"),
+    ("command_schedules", "List your scheduled forecasts to edit or remove them"),
+    ("command_pause", "Pause your scheduled forecasts, optionally until a date"),
+    ("command_resume", "Resume your paused forecasts"),
+    ("command_set_offset", "Set your offset respect UTC"),
+    ("command_current_offset", "Show your offset respect UTC"),
+    ("command_group_default", "Provides weather info from the group default city"),
+    ("command_set_group_city", "Set the group default city (group admins)"),
+    ("command_set_group_offset", "Set the group offset respect UTC (group admins)"),
+    ("command_set_group_language", "Set the language of the group forecasts (group admins)"),
+    ("command_set_group_units", "Set the units of the group forecasts (group admins)"),
+    (
+        "command_schedule_group",
+        "Schedule a forecast posted to the group on chosen days (group admins)",
+    ),
+    ("command_unschedule_group", "Remove all the group scheduled forecasts (group admins)"),
+    ("command_live", "Post a pinned weather message of the default city that keeps updating"),
+    ("command_stop_live", "Stop updating the live weather message"),
+    ("command_language", "Choose the language of the bot, like /language es"),
+    ("command_units", "Choose metric, imperial or Kelvin units, like /units imperial"),
+    ("command_cancel", "Cancel the current operation"),
+];
@@ -0,0 +1,351 @@
+// Spanish texts.
+pub static MESSAGES: &[(&str, &str)] = &[
+    ("greeting", "¡Hola, {}!\n{}"),
+    // Help
+    (
+        "help_header",
+        "Este bot da información del tiempo de todo el mundo.\nPara usarlo escribe el comando:\n\n",
+    ),
+    (
+        "help_footer",
+        "\nMe encantaría que echaras un vistazo a mi GitHub y vieras cuánto trabajo hay en este bot.\n\
+         Si te gusta, considera darle una estrella en GitHub o, si quieres ejecutarlo por tu cuenta, haz un fork del proyecto.\n\
+         <a href=\"https://github.com/pxp9/weather_bot_rust\">Repositorio de RustWeatherBot en GitHub</a>",
+    ),
+    // Weather
+    (
+        "weather",
+        "\n🌍🌍 Tiempo: {}\n🌡️🌡️ Temperatura media: {}\n🧊🧊 Temperatura mínima: {}\n🔥🔥 Temperatura máxima: {}\n⛰️⛰️ Presión: {} hPa\n💧💧 Humedad: {} %\n💨💨 Viento: {}",
+    ),
+    (
+        "forecast",
+        "\n==== {} ====\n🌍🌍 Tiempo: {}\n🌡️🌡️ Temperatura media: {}\n🧊🧊 Temperatura mínima: {}\n🔥🔥 Temperatura máxima: {}\n⛰️⛰️ Presión: {} hPa\n💧💧 Humedad: {} %\n💨💨 Viento: {}\n Probabilidad de lluvia: {} %",
+    ),
+    ("location_weather", "Tu ubicación, cerca de {}\nLat {} , Lon {}\n{}"),
+    (
+        "scheduled_weather",
+        "¡Aquí tienes tu previsión programada!\n\n {},{}\nLat {} , Lon {}\n{}",
+    ),
+    (
+        "live_weather",
+        "Tiempo en vivo, actualizado a las {} UTC\n\n{},{}\nLat {} , Lon {}\n{}",
+    ),
+    // Delivery days
+    ("monday", "lunes"),
+    ("tuesday", "martes"),
+    ("wednesday", "miércoles"),
+    ("thursday", "jueves"),
+    ("friday", "viernes"),
+    ("saturday", "sábado"),
+    ("sunday", "domingo"),
+    ("every_day", "todos los días"),
+    ("on_weekdays", "entre semana"),
+    ("on_weekends", "los fines de semana"),
+    ("every_weekday", "cada {}"),
+    ("on_days", "los {} y {}"),
+    ("no_days", "nunca"),
+    ("schedule_at", "{} a las {}"),
+    // Cities
+    (
+        "write_city",
+        "Escribe una ciudad, a ver si la encuentro. También puedes compartir una ubicación",
+    ),
+    (
+        "city_not_found_write_again",
+        "No encontré tu ciudad {}. Escribe una ciudad, a ver si la encuentro. También puedes compartir una ubicación",
+    ),
+    (
+        "city_not_found_cancelled",
+        "No encontré tu ciudad {}. Comando cancelado.",
+    ),
+    ("select_city", "Encontré estas ciudades. Elige una"),
+    (
+        "select_city_from_list",
+        "Por favor elige una de las ciudades de la lista. El comando fue cancelado",
+    ),
+    (
+        "unknown_link_city",
+        "No encontré la ciudad de este enlace. Mira /start para ver los comandos disponibles",
+    ),
+    // Default city and offset
+    ("default_city_is", "Tu ciudad por defecto es {}"),
+    ("no_default_city", "No tienes ciudad por defecto"),
+    ("setting_default_city", "Estableciendo la ciudad por defecto..."),
+    ("city_updated.your", "Tu ciudad por defecto fue actualizada"),
+    (
+        "city_updated.group",
+        "La ciudad por defecto del grupo fue actualizada",
+    ),
+    (
+        "city_updated.channel",
+        "La ciudad por defecto del canal fue actualizada",
+    ),
+    ("offset_is", "Tu diferencia horaria es UTC {}"),
+    (
+        "no_offset",
+        "No tienes diferencia horaria. Por favor ejecuta /set_offset",
+    ),
+    (
+        "offset_question",
+        "¿Qué diferencia horaria tienes con UTC?\n(0 si tu zona horaria es UTC, 2 si es UTC + 2 , -2 si es UTC - 2, [-11,12])",
+    ),
+    (
+        "invalid_offset",
+        "Esa diferencia horaria no es válida, tiene que ser un número entre -11 y 12.\n\
+         Si tu zona horaria es UTC + 2 pon 2, si es UTC - 10 pon -10, 0 si es UTC.\n\
+         El comando fue cancelado",
+    ),
+    ("offset_set.your", "Tu diferencia horaria ahora es {}"),
+    (
+        "offset_set.group",
+        "La diferencia horaria del grupo ahora es {}",
+    ),
+    (
+        "offset_set.channel",
+        "La diferencia horaria del canal ahora es {}",
+    ),
+    // Scheduled forecasts
+    (
+        "schedule_city_question",
+        "¿De qué ciudad quieres programar la previsión? También puedes compartir una ubicación",
+    ),
+    (
+        "schedule_time_question",
+        "¿A qué hora quieres programarla? (formato hora:minutos entre 0-23:0-59)\n\
+         Todos los días por defecto, añade los días en inglés como `07:30 weekdays`, `Sat,Sun at 09:00` o `07:30 every Monday`",
+    ),
+    (
+        "invalid_time",
+        "Esa hora no tiene el formato `hora:minutos` con la hora entre 0 y 23 \
+         y los minutos entre 0 y 59, opcionalmente con los días como `07:30 weekdays`. El comando fue cancelado",
+    ),
+    (
+        "schedule_without_offset",
+        "No puedes programar sin diferencia horaria. Por favor ejecuta /set_offset",
+    ),
+    ("forecast_scheduled", "Previsión programada {} UTC {}"),
+    (
+        "forecasts_unscheduled.your",
+        "Tus previsiones fueron eliminadas",
+    ),
+    (
+        "forecasts_unscheduled.group",
+        "Las previsiones del grupo fueron eliminadas",
+    ),
+    (
+        "forecasts_unscheduled.channel",
+        "Las previsiones del canal fueron eliminadas",
+    ),
+    (
+        "no_scheduled_forecasts",
+        "No hay previsiones programadas. Ejecuta /schedule para añadir una",
+    ),
+    (
+        "scheduled_forecasts.your",
+        "Tus previsiones programadas, horas en UTC {}\n",
+    ),
+    (
+        "scheduled_forecasts.group",
+        "Previsiones programadas del grupo, horas en UTC {}\n",
+    ),
+    (
+        "scheduled_forecasts.channel",
+        "Previsiones programadas del canal, horas en UTC {}\n",
+    ),
+    (
+        "scheduled_forecast",
+        "\n{}. {} {}\nPróximo envío: {}\nÚltimo envío: {}\n",
+    ),
+    ("never_delivered", "nunca"),
+    ("paused", "en pausa"),
+    ("paused_until", "en pausa hasta {}"),
+    ("edit_button", "Editar {}"),
+    ("remove_button", "Eliminar {}"),
+    ("forecast_removed", "La previsión de {} fue eliminada"),
+    (
+        "forecast_not_found",
+        "Esa previsión ya no existe. Ejecuta /schedules para verlas",
+    ),
+    (
+        "invalid_pause_date",
+        "Esa fecha no es válida, tiene que ser una fecha futura como /pause until 2026-08-31",
+    ),
+    (
+        "forecasts_paused_until",
+        "{} previsiones en pausa hasta {}. Ejecuta /resume para reanudarlas antes",
+    ),
+    (
+        "forecasts_paused",
+        "{} previsiones en pausa. Ejecuta /resume para reanudarlas",
+    ),
+    ("no_paused_forecasts", "No hay previsiones en pausa"),
+    ("forecasts_resumed", "{} previsiones reanudadas"),
+    // Groups
+    (
+        "group_admins_only",
+        "Solo los administradores del grupo pueden cambiar su configuración",
+    ),
+    (
+        "group_schedule_without_offset",
+        "El grupo no puede programar sin diferencia horaria. Por favor ejecuta /set_group_offset",
+    ),
+    (
+        "group_no_default_city",
+        "El grupo no tiene ciudad por defecto. Un administrador puede establecerla con /set_group_city",
+    ),
+    ("only_groups", "Este comando solo funciona en grupos"),
+    // Live weather
+    (
+        "live_no_default_city",
+        "No hay ciudad por defecto. Por favor ejecuta /set_default_city",
+    ),
+    (
+        "live_pin_failed",
+        "No pude fijar el mensaje en vivo, necesito permiso para fijar mensajes",
+    ),
+    (
+        "live_not_found",
+        "No hay ningún mensaje del tiempo en vivo en este chat",
+    ),
+    (
+        "live_stopped",
+        "El mensaje del tiempo en vivo ya no se actualizará",
+    ),
+    // Favorites
+    ("favorite_cities.your", "Tus ciudades favoritas\n"),
+    ("favorite_cities.group", "Las ciudades favoritas del grupo\n"),
+    ("favorite_cities.channel", "Las ciudades favoritas del canal\n"),
+    ("favorite_weather", "\n⭐ {}: {}, {}"),
+    ("favorite_weather_unavailable", "\n⭐ {}: tiempo no disponible"),
+    (
+        "favorites_limit",
+        "Puedes tener hasta {} ciudades favoritas. Ejecuta /remove_favorite para hacer sitio",
+    ),
+    (
+        "add_favorite_question",
+        "¿Qué ciudad quieres añadir a tus favoritas? También puedes compartir una ubicación",
+    ),
+    (
+        "favorite_added",
+        "{} se añadió a tus favoritas. Ejecuta /favorites para ver su tiempo",
+    ),
+    ("favorite_exists", "{} ya es una de tus favoritas"),
+    ("favorite_removed", "{} se quitó de tus favoritas"),
+    ("select_favorite_to_remove", "Elige la favorita que quieres quitar"),
+    (
+        "select_favorite_to_rename",
+        "Elige la favorita que quieres renombrar",
+    ),
+    (
+        "favorite_name_question",
+        "¿Cómo quieres llamar a {}? (hasta {} caracteres, como Casa u Oficina)",
+    ),
+    (
+        "invalid_favorite_name",
+        "Ese nombre no es válido, tiene que tener entre 1 y {} caracteres. El comando fue cancelado",
+    ),
+    ("favorite_renamed", "Tu favorita ahora se llama {}"),
+    (
+        "no_favorites",
+        "No tienes ciudades favoritas. Ejecuta /add_favorite para añadir una",
+    ),
+    (
+        "favorite_not_found",
+        "Esa favorita ya no existe. Ejecuta /favorites para verlas",
+    ),
+    // Compare
+    ("compare_city", "Ciudad"),
+    ("compare_temp", "Temp"),
+    ("compare_humidity", "Humedad"),
+    ("compare_rain", "Lluvia 9h"),
+    ("compare_warmest", "🔥 Más cálida: {} {}, {}"),
+    ("compare_driest", "🏜️ Más seca: {} {} % de humedad"),
+    (
+        "compare_rainiest",
+        "☔ Mayor probabilidad de lluvia: {} {} %",
+    ),
+    ("compare_not_found", "No encontré {}"),
+    (
+        "compare_ambiguous",
+        "{} coincide con varias ciudades, añade el código del país después de una coma",
+    ),
+    (
+        "compare_truncated",
+        "Solo se comparan las primeras {} ciudades",
+    ),
+    ("compare_unavailable", "El tiempo de {} no está disponible"),
+    (
+        "compare_usage",
+        "Escribe al menos dos ciudades para comparar, como /compare Madrid, Lisboa, París, FR. Sin ciudades se comparan tus favoritas",
+    ),
+    // Language
+    ("language_question", "¿En qué idioma quieres que hable?"),
+    ("language_set", "A partir de ahora hablo español"),
+    ("group_language_set", "A partir de ahora las previsiones del grupo se envían en {}"),
+    (
+        "unknown_language",
+        "Todavía no hablo ese idioma. Ejecuta /language para ver los disponibles",
+    ),
+    // Units
+    ("units_question", "¿Qué unidades quieres usar?"),
+    ("group_units_set", "A partir de ahora las previsiones del grupo se muestran en {}"),
+    ("units_set", "A partir de ahora el tiempo se muestra en {}"),
+    (
+        "unknown_units",
+        "No conozco esas unidades. Ejecuta /units para ver las disponibles",
+    ),
+    // Other
+    ("operation_cancelled", "Tu operación fue cancelada"),
+    (
+        "unknown_command",
+        "Comando desconocido. Mira /start para ver los comandos disponibles",
+    ),
+    // Commands
+    ("command_start", "Muestra los comandos disponibles"),
+    ("command_find_city", "Consulta el tiempo de cualquier ciudad del mundo"),
+    ("command_default", "El tiempo de tu ciudad por defecto"),
+    ("command_set_default_city", "Establece tu ciudad por defecto"),
+    ("command_current_default_city", "Muestra tu ciudad por defecto"),
+    ("command_favorites", "El tiempo de todas tus ciudades favoritas"),
+    ("command_add_favorite", "Añade una ciudad a tus favoritas"),
+    ("command_remove_favorite", "Quita una ciudad de tus favoritas"),
+    ("command_rename_favorite", "Pon un nombre como Casa u Oficina a una de tus favoritas"),
+    ("command_compare", "Compara el tiempo de varias ciudades, como /compare Madrid, Lisboa"),
+    (
+        "command_schedule",
+        "Programa una previsión de una ciudad todos los días, entre semana o en los días elegidos",
+    ),
+    ("command_unschedule", "Elimina todas tus previsiones programadas"),
+    ("command_schedules", "Lista tus previsiones programadas para editarlas o eliminarlas"),
+    ("command_pause", "Pausa tus previsiones programadas, opcionalmente hasta una fecha"),
+    ("command_resume", "Reanuda tus previsiones pausadas"),
+    ("command_set_offset", "Establece tu diferencia horaria con UTC"),
+    ("command_current_offset", "Muestra tu diferencia horaria con UTC"),
+    ("command_group_default", "El tiempo de la ciudad por defecto del grupo"),
+    ("command_set_group_city", "Establece la ciudad por defecto del grupo (administradores)"),
+    (
+        "command_set_group_offset",
+        "Establece la diferencia horaria del grupo con UTC (administradores)",
+    ),
+    (
+        "command_set_group_language",
+        "Establece el idioma de las previsiones del grupo (administradores)",
+    ),
+    (
+        "command_set_group_units",
+        "Establece las unidades de las previsiones del grupo (administradores)",
+    ),
+    (
+        "command_schedule_group",
+        "Programa una previsión para el grupo en los días elegidos (administradores)",
+    ),
+    ("command_unschedule_group", "Elimina las previsiones programadas del grupo (administradores)"),
+    (
+        "command_live",
+        "Publica un mensaje fijado con el tiempo de la ciudad por defecto que se actualiza",
+    ),
+    ("command_stop_live", "Deja de actualizar el mensaje del tiempo en vivo"),
+    ("command_language", "Elige el idioma del bot, como /language es"),
+    ("command_units", "Elige unidades métricas, imperiales o Kelvin, como /units imperial"),
+    ("command_cancel", "Cancela la operación actual"),
+];
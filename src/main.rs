@@ -1,14 +1,84 @@
+use std::time::Duration;
+use weather_bot_rust::command::registry;
+use weather_bot_rust::db::Repo;
+use weather_bot_rust::shutdown;
+use weather_bot_rust::telegram::client::ApiClient;
 use weather_bot_rust::telegram::handler::Handler;
+use weather_bot_rust::telegram::webhook::WebhookServer;
 use weather_bot_rust::workers;
+use weather_bot_rust::BOT_MODE;
+use weather_bot_rust::SHUTDOWN_TIMEOUT;
+
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(2);
 
 #[tokio::main]
 async fn main() {
     pretty_env_logger::init();
 
     log::info!("Starting bot");
-    workers::start_workers().await.unwrap();
 
-    let mut handler = Handler::new().await;
+    let clients = ApiClient::api_clients().await.unwrap();
+
+    // Rows stored before multi-bot support belong to the first configured bot.
+    let adopted = Repo::repo()
+        .await
+        .unwrap()
+        .adopt_legacy_rows(clients[0].bot_id())
+        .await
+        .unwrap();
+
+    if adopted > 0 {
+        log::info!("Assigned {} rows to @{}", adopted, clients[0].bot_name());
+    }
+
+    let workers = workers::start_workers().await.unwrap();
+
+    if let Err(err) = registry::register_commands().await {
+        log::error!("Failed to register bot commands {:?}", err);
+    }
+
+    tokio::spawn(shutdown::listen());
+
+    match BOT_MODE.as_str() {
+        "webhook" => {
+            log::info!("Receiving updates with webhook");
+
+            let server = WebhookServer::new().await.unwrap();
+
+            server.start().await.unwrap();
+        }
+        _ => {
+            log::info!("Receiving updates with long polling");
+
+            let mut handles = Vec::new();
+
+            for client in clients.iter() {
+                let mut handler = Handler::new(client).await.unwrap();
+
+                handles.push(tokio::spawn(async move { handler.start().await }));
+            }
+
+            for handle in handles {
+                handle.await.unwrap();
+            }
+        }
+    }
+
+    log::info!("Waiting for the running tasks to finish");
+
+    let unfinished = shutdown::drain(*SHUTDOWN_TIMEOUT).await;
+
+    if unfinished > 0 {
+        log::warn!(
+            "{} tasks did not finish in time, they will run again on the next start",
+            unfinished
+        );
+    }
+
+    // The running tasks are done, the workers only have to record their results.
+    workers.stop(CLOSE_TIMEOUT).await;
+
+    Repo::repo().await.unwrap().close(CLOSE_TIMEOUT).await;
 
-    handler.start().await;
+    log::info!("Bot stopped");
 }
@@ -1,20 +1,34 @@
+use super::units::Units;
+use super::weather::City;
 use super::weather::Weather;
 use super::weather::WeatherForecast;
+use crate::locale::Language;
 use crate::OPEN_WEATHER_MAP_API_TOKEN;
 use fang::FangError;
 use reqwest::Client;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
 use thiserror::Error;
 use tokio::sync::OnceCell;
 use typed_builder::TypedBuilder;
 
-const UNITS: &str = "metric";
-const LANG: &str = "en";
+// Current weather of a city is reused for 10 minutes,
+// OpenWeatherMap does not refresh it more often than that.
+const CACHE_TTL: Duration = Duration::from_secs(600);
 
 static WEATHER_CLIENT: OnceCell<WeatherApiClient> = OnceCell::const_new();
 
+// Weather of a city in a language and units, and when it was fetched.
+type WeatherCache = HashMap<(i32, Language, Units), (Instant, Weather)>;
+
 #[derive(TypedBuilder, Clone)]
 pub struct WeatherApiClient {
     client: Client,
+    #[builder(default)]
+    cache: Arc<Mutex<WeatherCache>>,
 }
 
 #[derive(Debug, Error)]
@@ -42,31 +56,99 @@ impl WeatherApiClient {
     pub async fn new() -> Self {
         WeatherApiClient::builder().client(Client::new()).build()
     }
-    pub async fn fetch(&self, lat: f64, lon: f64) -> Result<Weather, ClientError> {
+    // The weather descriptions come translated to the language
+    // and the values converted to the units.
+    pub async fn fetch(
+        &self,
+        lat: f64,
+        lon: f64,
+        language: Language,
+        units: Units,
+    ) -> Result<Weather, ClientError> {
         let request_url = format!(
             "https://api.openweathermap.org/data/2.5/weather?lat={}&lon={}&appid={}&units={}&lang={}",
-            lat, lon, OPEN_WEATHER_MAP_API_TOKEN.as_str(), UNITS, LANG
+            lat,
+            lon,
+            OPEN_WEATHER_MAP_API_TOKEN.as_str(),
+            units.api_name(),
+            language.code()
         );
 
         let response = self.client.get(&request_url).send()?;
 
-        Self::decode_response(response)
+        let mut weather = Self::decode_response(response)?;
+        weather.language = language;
+        weather.units = units;
+
+        Ok(weather)
+    }
+
+    pub async fn fetch_cached(
+        &self,
+        city: &City,
+        language: Language,
+        units: Units,
+    ) -> Result<Weather, ClientError> {
+        let key = (city.id, language, units);
+
+        if let Some((fetched_at, weather)) = self.cache.lock().unwrap().get(&key) {
+            if fetched_at.elapsed() < CACHE_TTL {
+                return Ok(weather.clone());
+            }
+        }
+
+        let weather = self
+            .fetch(city.coord.lat, city.coord.lon, language, units)
+            .await?;
+
+        let mut cache = self.cache.lock().unwrap();
+
+        // Stale entries are dropped here, otherwise every city ever queried stays in memory.
+        cache.retain(|_, (fetched_at, _)| fetched_at.elapsed() < CACHE_TTL);
+        cache.insert(key, (Instant::now(), weather.clone()));
+
+        Ok(weather)
     }
 
-    pub async fn fetch_weekly(&self, lat: f64, lon: f64) -> Result<WeatherForecast, ClientError> {
+    pub async fn fetch_weekly(
+        &self,
+        lat: f64,
+        lon: f64,
+        language: Language,
+        units: Units,
+    ) -> Result<WeatherForecast, ClientError> {
+        self.fetch_weekly_blocking(lat, lon, language, units)
+    }
+
+    // The request blocks the thread until the API answers,
+    // call it from `tokio::task::spawn_blocking`.
+    pub fn fetch_weekly_blocking(
+        &self,
+        lat: f64,
+        lon: f64,
+        language: Language,
+        units: Units,
+    ) -> Result<WeatherForecast, ClientError> {
         let request_url = format!(
             "https://api.openweathermap.org/data/2.5/forecast?lat={}&lon={}&appid={}&units={}&lang={}&cnt={}",
             lat,
             lon,
             OPEN_WEATHER_MAP_API_TOKEN.as_str(),
-            UNITS,
-            LANG,
+            units.api_name(),
+            language.code(),
             9,
         );
 
         let response = self.client.get(&request_url).send()?;
 
-        Self::decode_weekly_response(response)
+        let mut weather_forecast = Self::decode_weekly_response(response)?;
+
+        for forecast in weather_forecast.list.iter_mut() {
+            forecast.language = language;
+            forecast.units = units;
+        }
+
+        Ok(weather_forecast)
     }
 
     fn decode_weekly_response(
@@ -0,0 +1,99 @@
+use std::fmt;
+
+/// Unit system of the weather, OpenWeatherMap converts the values to it.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, Default)]
+pub enum Units {
+    #[default]
+    Metric,
+    Imperial,
+    Kelvin,
+}
+
+impl Units {
+    pub const ALL: [Units; 3] = [Units::Metric, Units::Imperial, Units::Kelvin];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Units::Metric => "metric",
+            Units::Imperial => "imperial",
+            Units::Kelvin => "kelvin",
+        }
+    }
+
+    /// Value of the `units` parameter of the API, Kelvin is its standard system.
+    pub fn api_name(&self) -> &'static str {
+        match self {
+            Units::Metric => "metric",
+            Units::Imperial => "imperial",
+            Units::Kelvin => "standard",
+        }
+    }
+
+    pub fn temperature_symbol(&self) -> &'static str {
+        match self {
+            Units::Metric => "ºC",
+            Units::Imperial => "ºF",
+            Units::Kelvin => "K",
+        }
+    }
+
+    pub fn speed_symbol(&self) -> &'static str {
+        match self {
+            Units::Imperial => "mph",
+            Units::Metric | Units::Kelvin => "m/s",
+        }
+    }
+
+    pub fn temperature(&self, value: f64) -> String {
+        format!("{} {}", value, self.temperature_symbol())
+    }
+
+    pub fn speed(&self, value: f64) -> String {
+        format!("{} {}", value, self.speed_symbol())
+    }
+
+    /// Accepts the codes and the usual names like `fahrenheit` or `celsius`.
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code.trim().to_lowercase().as_str() {
+            "metric" | "celsius" | "c" => Some(Units::Metric),
+            "imperial" | "fahrenheit" | "f" => Some(Units::Imperial),
+            "kelvin" | "standard" | "k" => Some(Units::Kelvin),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Units {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}, {})",
+            self.code(),
+            self.temperature_symbol(),
+            self.speed_symbol()
+        )
+    }
+}
+
+#[cfg(test)]
+mod units_test {
+    use super::*;
+
+    #[test]
+    fn test_from_code() {
+        assert_eq!(Units::from_code("Fahrenheit"), Some(Units::Imperial));
+        assert_eq!(Units::from_code(" metric "), Some(Units::Metric));
+        assert_eq!(Units::from_code("k"), Some(Units::Kelvin));
+        assert_eq!(Units::from_code("rankine"), None);
+
+        for units in Units::ALL {
+            assert_eq!(Units::from_code(units.code()), Some(units));
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Units::Imperial.to_string(), "imperial (ºF, mph)");
+        assert_eq!(Units::Kelvin.to_string(), "kelvin (K, m/s)");
+    }
+}
@@ -1,3 +1,5 @@
+use super::units::Units;
+use crate::locale::Language;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use typed_builder::TypedBuilder;
@@ -21,6 +23,12 @@ pub struct Forecast {
     pub rain: Option<Rain>,
     #[serde(default)]
     pub snow: Option<Snow>,
+    // Language of the request, the labels are written in it.
+    #[serde(skip)]
+    pub language: Language,
+    // Units of the request, the values come converted to them.
+    #[serde(skip)]
+    pub units: Units,
 }
 
 impl fmt::Display for Forecast {
@@ -29,18 +37,29 @@ impl fmt::Display for Forecast {
         if !self.weather.is_empty() {
             weather_desc = &self.weather[0].description;
         }
-        let temp = self.main.temp;
-        let temp_min = self.main.temp_min;
-        let temp_max = self.main.temp_max;
+        let temp = self.units.temperature(self.main.temp);
+        let temp_min = self.units.temperature(self.main.temp_min);
+        let temp_max = self.units.temperature(self.main.temp_max);
         let pressure = self.main.pressure;
         let humidity = self.main.humidity;
+        let wind = self.units.speed(self.wind.speed);
         let pop = self.pop * 100.0;
         let dt = self.dt_txt.clone();
 
-        let st: String = format!(
-        "\n==== {} ====\n🌍🌍 Weather: {}\n🌡️🌡️ Mean Temperature: {} ºC\n🧊🧊 Minimum temperature: {} ºC\n🔥🔥 Maximum temperature: {} ºC\n⛰️⛰️ Pressure: {} hPa\n💧💧 Humidity: {} %\n Rain probability: {} %",
-        dt, weather_desc, temp, temp_min, temp_max, pressure, humidity, pop
-	);
+        let st: String = self.language.format(
+            "forecast",
+            &[
+                &dt,
+                &weather_desc,
+                &temp,
+                &temp_min,
+                &temp_max,
+                &pressure,
+                &humidity,
+                &wind,
+                &pop,
+            ],
+        );
 
         write!(f, "{}", st)
     }
@@ -69,6 +88,12 @@ pub struct Weather {
     pub id: u32,
     pub name: String,
     pub cod: u32,
+    // Language of the request, the labels are written in it.
+    #[serde(skip)]
+    pub language: Language,
+    // Units of the request, the values come converted to them.
+    #[serde(skip)]
+    pub units: Units,
 }
 
 impl fmt::Display for Weather {
@@ -77,16 +102,25 @@ impl fmt::Display for Weather {
         if !self.weather.is_empty() {
             weather_desc = &self.weather[0].description;
         }
-        let temp = self.main.temp;
-        let temp_min = self.main.temp_min;
-        let temp_max = self.main.temp_max;
+        let temp = self.units.temperature(self.main.temp);
+        let temp_min = self.units.temperature(self.main.temp_min);
+        let temp_max = self.units.temperature(self.main.temp_max);
         let pressure = self.main.pressure;
         let humidity = self.main.humidity;
-
-        let st: String = format!(
-        "\n🌍🌍 Weather: {}\n🌡️🌡️ Mean Temperature: {} ºC\n🧊🧊 Minimum temperature: {} ºC\n🔥🔥 Maximum temperature: {} ºC\n⛰️⛰️ Pressure: {} hPa\n💧💧 Humidity: {} %",
-        weather_desc, temp, temp_min, temp_max, pressure, humidity
-	);
+        let wind = self.units.speed(self.wind.speed);
+
+        let st: String = self.language.format(
+            "weather",
+            &[
+                &weather_desc,
+                &temp,
+                &temp_min,
+                &temp_max,
+                &pressure,
+                &humidity,
+                &wind,
+            ],
+        );
 
         write!(f, "{}", st)
     }
@@ -125,6 +159,16 @@ pub struct City {
     pub coord: Coord,
 }
 
+impl City {
+    pub fn short_name(&self) -> String {
+        if self.state.is_empty() {
+            format!("{},{}", self.name, self.country)
+        } else {
+            format!("{},{},{}", self.name, self.country, self.state)
+        }
+    }
+}
+
 impl fmt::Display for City {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if self.state.is_empty() {
@@ -0,0 +1 @@
+UPDATE chats SET active = TRUE WHERE id = $1 AND bot_id = $2
@@ -0,0 +1 @@
+UPDATE chats SET bot_id = $1 WHERE bot_id = 0
@@ -0,0 +1 @@
+UPDATE processed_updates SET bot_id = $1 WHERE bot_id = 0
@@ -0,0 +1 @@
+UPDATE update_offsets SET bot_id = $1 WHERE bot_id = 0
@@ -0,0 +1 @@
+SELECT EXISTS (SELECT 1 FROM processed_updates WHERE update_id = $1 AND bot_id = $2)
@@ -1 +1 @@
-SELECT state FROM chats WHERE id = $1 AND user_id = $2
+SELECT state FROM chats WHERE id = $1 AND user_id = $2 AND bot_id = $3
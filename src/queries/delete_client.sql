@@ -1 +1 @@
-DELETE FROM chats WHERE id = $1 AND user_id = $2
+DELETE FROM chats WHERE id = $1 AND user_id = $2 AND bot_id = $3
@@ -0,0 +1 @@
+DELETE FROM forecasts WHERE id = $1 AND chat_id = $2 AND user_id = $3 AND bot_id = $4 RETURNING *
@@ -1 +1 @@
-DELETE FROM forecasts WHERE chat_id = $1 AND user_id = $2 RETURNING *
+DELETE FROM forecasts WHERE chat_id = $1 AND user_id = $2 AND bot_id = $3 RETURNING *
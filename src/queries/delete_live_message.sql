@@ -0,0 +1 @@
+DELETE FROM live_messages WHERE chat_id = $1 AND message_id = $2 AND bot_id = $3
@@ -0,0 +1 @@
+DELETE FROM processed_updates WHERE processed_at < $1
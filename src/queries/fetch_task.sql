@@ -0,0 +1,14 @@
+UPDATE "fang_tasks" SET "state" = 'in_progress', "updated_at" = $2, "worker_id" = $4
+WHERE "id" = (
+    SELECT "id" FROM "fang_tasks" AS "task"
+    WHERE "task_type" = $1 AND "state" IN ('new', 'retried') AND $2 >= "scheduled_at"
+    AND NOT ($3 AND EXISTS (
+        SELECT 1 FROM "fang_tasks" AS "earlier"
+        WHERE "earlier"."task_type" = "task"."task_type"
+        AND "earlier"."metadata" -> 'chat_id' = "task"."metadata" -> 'chat_id'
+        AND "earlier"."created_at" < "task"."created_at"
+        AND "earlier"."state" IN ('new', 'in_progress', 'retried')
+    ))
+    ORDER BY "created_at" ASC, "scheduled_at" ASC LIMIT 1 FOR UPDATE SKIP LOCKED
+)
+RETURNING *
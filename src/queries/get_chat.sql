@@ -1 +1 @@
-SELECT state , default_city_id , selected, "offset" FROM chats WHERE id = $1 AND user_id = $2
+SELECT state , default_city_id , selected, "offset", active, editing_group, language, units FROM chats WHERE id = $1 AND user_id = $2 AND bot_id = $3
@@ -0,0 +1 @@
+SELECT id , name , country , state, lon, lat FROM cities WHERE UPPER(name) = UPPER($1) AND ($2::VARCHAR IS NULL OR UPPER(country) = UPPER($2)) ORDER BY name , country , state LIMIT 30
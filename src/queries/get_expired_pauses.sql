@@ -0,0 +1 @@
+SELECT * FROM forecasts WHERE paused = TRUE AND paused_until <= $1 AND bot_id = $2
@@ -0,0 +1 @@
+SELECT * FROM favorites WHERE chat_id = $1 AND user_id = $2 AND bot_id = $3 ORDER BY id
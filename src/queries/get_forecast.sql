@@ -1 +1 @@
-SELECT * FROM forecasts WHERE chat_id = $1 AND user_id = $2 AND city_id = $3
+SELECT * FROM forecasts WHERE chat_id = $1 AND user_id = $2 AND city_id = $3 AND bot_id = $4
@@ -1 +1 @@
-SELECT * FROM forecasts WHERE next_delivery_at <= $1
+SELECT * FROM forecasts WHERE next_delivery_at <= $1 AND suspended = FALSE AND paused = FALSE AND bot_id = $2
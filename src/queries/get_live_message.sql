@@ -0,0 +1 @@
+SELECT * FROM live_messages WHERE chat_id = $1 AND bot_id = $2
@@ -0,0 +1 @@
+SELECT * FROM live_messages WHERE next_refresh_at <= $1 AND bot_id = $2
@@ -0,0 +1,4 @@
+SELECT id , name , country , state , lon , lat FROM cities
+WHERE lat BETWEEN $1::float8 - $3::float8 AND $1 + $3
+AND LEAST(ABS(lon - $2), 360 - ABS(lon - $2)) <= $3 / GREATEST(COS(RADIANS($1)), 0.01)
+ORDER BY (lat - $1) ^ 2 + (LEAST(ABS(lon - $2), 360 - ABS(lon - $2)) * COS(RADIANS($1))) ^ 2 LIMIT 1
@@ -0,0 +1 @@
+SELECT * FROM forecasts WHERE chat_id = $1 AND user_id = $2 AND paused = TRUE AND bot_id = $3
@@ -0,0 +1 @@
+SELECT * FROM forecasts WHERE chat_id = $1 AND suspended = TRUE AND bot_id = $2
@@ -0,0 +1 @@
+SELECT update_id FROM update_offsets WHERE bot_id = $1
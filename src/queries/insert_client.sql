@@ -1 +1 @@
-INSERT INTO chats (id , state , user_id ) VALUES ($1 , $2 , $3)
+INSERT INTO chats (id , state , user_id , bot_id ) VALUES ($1 , $2 , $3 , $4)
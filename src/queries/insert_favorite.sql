@@ -0,0 +1 @@
+INSERT INTO favorites (chat_id, user_id, city_id, bot_id) VALUES ($1, $2, $3, $4) ON CONFLICT DO NOTHING RETURNING *
@@ -0,0 +1 @@
+INSERT INTO live_messages (chat_id, message_id, city_id, next_refresh_at, updated_at, language, units, bot_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) ON CONFLICT (bot_id, chat_id) DO UPDATE SET message_id = EXCLUDED.message_id, city_id = EXCLUDED.city_id, next_refresh_at = EXCLUDED.next_refresh_at, updated_at = EXCLUDED.updated_at, language = EXCLUDED.language, units = EXCLUDED.units, created_at = EXCLUDED.updated_at RETURNING *
@@ -0,0 +1 @@
+INSERT INTO processed_updates (update_id, processed_at, bot_id) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING
@@ -1 +1 @@
-UPDATE chats SET default_city_id = $1 WHERE id = $2 AND user_id = $3
+UPDATE chats SET default_city_id = $1 WHERE id = $2 AND user_id = $3 AND bot_id = $4
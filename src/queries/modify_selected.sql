@@ -1 +1 @@
-UPDATE chats SET selected = $1 WHERE id = $2 AND user_id = $3
+UPDATE chats SET selected = $1 WHERE id = $2 AND user_id = $3 AND bot_id = $4
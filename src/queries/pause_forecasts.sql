@@ -0,0 +1 @@
+UPDATE forecasts SET paused = TRUE, paused_until = $3 WHERE chat_id = $1 AND user_id = $2 AND bot_id = $4 RETURNING *
@@ -0,0 +1 @@
+UPDATE "fang_tasks" SET "state" = 'retried', "updated_at" = $2 WHERE "id" = $1
@@ -0,0 +1 @@
+UPDATE "fang_tasks" SET "state" = 'retried', "updated_at" = $1 WHERE "state" = 'in_progress' AND ("worker_id" = $2 OR "worker_id" IS NULL)
@@ -0,0 +1 @@
+UPDATE forecasts SET cron_expression = $4, next_delivery_at = $5, updated_at = $6 WHERE id = $3 AND chat_id = $1 AND user_id = $2 AND bot_id = $7 RETURNING *
@@ -0,0 +1 @@
+UPDATE forecasts SET suspended = FALSE, next_delivery_at = $2 WHERE id = $1
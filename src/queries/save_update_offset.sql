@@ -0,0 +1 @@
+INSERT INTO update_offsets (update_id, updated_at, bot_id) VALUES ($1, $2, $3) ON CONFLICT (bot_id) DO UPDATE SET update_id = GREATEST(update_offsets.update_id, EXCLUDED.update_id), updated_at = EXCLUDED.updated_at
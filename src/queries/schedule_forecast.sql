@@ -0,0 +1 @@
+INSERT INTO forecasts (chat_id, user_id, city_id, cron_expression, next_delivery_at, updated_at, bot_id) VALUES ($1, $2, $3, $4, $5, $6, $7) ON CONFLICT (bot_id, chat_id, user_id, city_id) DO UPDATE SET cron_expression = EXCLUDED.cron_expression, next_delivery_at = EXCLUDED.next_delivery_at, updated_at = EXCLUDED.updated_at RETURNING *
@@ -0,0 +1 @@
+UPDATE forecasts SET suspended = TRUE WHERE chat_id = $1 AND bot_id = $2
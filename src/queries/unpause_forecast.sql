@@ -0,0 +1 @@
+UPDATE forecasts SET paused = FALSE, paused_until = NULL, next_delivery_at = $2 WHERE id = $1
@@ -0,0 +1 @@
+UPDATE live_messages SET next_refresh_at = $3, updated_at = $4 WHERE chat_id = $1 AND message_id = $2 AND bot_id = $5
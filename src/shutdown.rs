@@ -0,0 +1,176 @@
+use fang::FangError;
+use lazy_static::lazy_static;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use tokio::signal::unix::signal;
+use tokio::signal::unix::SignalKind;
+use tokio::sync::watch;
+use tokio::time::sleep;
+use tokio::time::Instant;
+
+const DRAIN_CHECK_PERIOD: Duration = Duration::from_millis(100);
+const SHUTTING_DOWN: &str = "The bot is shutting down";
+
+lazy_static! {
+    static ref SHUTDOWN: Shutdown = Shutdown::new();
+}
+
+/// Shutdown request and the task runs in flight.
+pub struct Shutdown {
+    sender: watch::Sender<bool>,
+    receiver: watch::Receiver<bool>,
+    running_tasks: AtomicUsize,
+}
+
+/// Marks a task run as in flight until it is dropped.
+pub struct RunningTask<'a> {
+    running_tasks: &'a AtomicUsize,
+}
+
+impl Drop for RunningTask<'_> {
+    fn drop(&mut self) {
+        self.running_tasks.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (sender, receiver) = watch::channel(false);
+
+        Self {
+            sender,
+            receiver,
+            running_tasks: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn request(&self) {
+        self.sender.send_replace(true);
+    }
+
+    pub fn is_requested(&self) -> bool {
+        *self.receiver.borrow()
+    }
+
+    /// Completes once the shutdown is requested.
+    pub async fn requested(&self) {
+        let mut receiver = self.receiver.clone();
+
+        while !*receiver.borrow_and_update() {
+            if receiver.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    /// Registers a task run, see [`start_task`].
+    pub fn start_task(&self) -> Result<RunningTask<'_>, FangError> {
+        self.running_tasks.fetch_add(1, Ordering::SeqCst);
+
+        let running_task = RunningTask {
+            running_tasks: &self.running_tasks,
+        };
+
+        if self.is_requested() {
+            return Err(FangError {
+                description: SHUTTING_DOWN.to_string(),
+            });
+        }
+
+        Ok(running_task)
+    }
+
+    /// Waits until the running tasks finish or the timeout expires.
+    /// Returns the number of tasks that did not finish.
+    pub async fn drain(&self, timeout: Duration) -> usize {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let running = self.running_tasks.load(Ordering::SeqCst);
+
+            if running == 0 || Instant::now() >= deadline {
+                return running;
+            }
+
+            sleep(DRAIN_CHECK_PERIOD).await;
+        }
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Waits for SIGTERM or SIGINT and requests the shutdown.
+pub async fn listen() {
+    let mut terminate = signal(SignalKind::terminate()).expect("Failed to listen to SIGTERM");
+    let mut interrupt = signal(SignalKind::interrupt()).expect("Failed to listen to SIGINT");
+
+    tokio::select! {
+        _ = terminate.recv() => log::info!("SIGTERM received"),
+        _ = interrupt.recv() => log::info!("SIGINT received"),
+    }
+
+    request();
+}
+
+pub fn request() {
+    SHUTDOWN.request();
+}
+
+pub fn is_requested() -> bool {
+    SHUTDOWN.is_requested()
+}
+
+/// Completes once the shutdown is requested.
+pub async fn requested() {
+    SHUTDOWN.requested().await
+}
+
+/// Registers a task run. After the shutdown is requested no task is started,
+/// the returned error puts the task back in the queue without counting the
+/// attempt, so it runs once the bot is running again.
+pub fn start_task() -> Result<RunningTask<'static>, FangError> {
+    SHUTDOWN.start_task()
+}
+
+pub fn is_shutdown_error(error: &FangError) -> bool {
+    error.description == SHUTTING_DOWN
+}
+
+/// Waits until the running tasks finish or the timeout expires.
+/// Returns the number of tasks that did not finish.
+pub async fn drain(timeout: Duration) -> usize {
+    SHUTDOWN.drain(timeout).await
+}
+
+#[cfg(test)]
+mod shutdown_test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_running_tasks() {
+        let shutdown = Shutdown::new();
+
+        let running_task = shutdown.start_task().unwrap();
+
+        shutdown.request();
+
+        assert!(shutdown.is_requested());
+        assert!(!is_requested());
+
+        let err = shutdown.start_task().err().unwrap();
+        assert!(is_shutdown_error(&err));
+
+        shutdown.requested().await;
+
+        assert_eq!(shutdown.drain(Duration::from_millis(200)).await, 1);
+
+        drop(running_task);
+
+        assert_eq!(shutdown.drain(Duration::from_millis(200)).await, 0);
+    }
+}
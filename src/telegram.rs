@@ -1,2 +1,5 @@
 pub mod client;
 pub mod handler;
+pub mod message_splitter;
+pub mod rate_limiter;
+pub mod webhook;
@@ -1,26 +1,75 @@
-use crate::RUST_TELEGRAM_BOT_TOKEN;
+use super::message_splitter;
+use super::rate_limiter::RateLimiter;
+use crate::db::LEGACY_BOT_ID;
+use crate::BOT_TOKENS;
+use fang::Deserialize;
 use fang::FangError;
+use fang::Serialize;
 use frankenstein::AllowedUpdate;
+use frankenstein::AnswerCallbackQueryParams;
+use frankenstein::AnswerInlineQueryParams;
 use frankenstein::AsyncApi;
 use frankenstein::AsyncTelegramApi;
+use frankenstein::BotCommand;
 use frankenstein::ChatAction;
+use frankenstein::ChatMember;
+use frankenstein::DeleteWebhookParams;
+use frankenstein::EditMessageTextParams;
+use frankenstein::ErrorResponse;
+use frankenstein::GetChatMemberParams;
+use frankenstein::GetChatParams;
 use frankenstein::GetUpdatesParams;
+use frankenstein::InlineKeyboardMarkup;
+use frankenstein::InlineQueryResult;
 use frankenstein::Message;
 use frankenstein::MethodResponse;
 use frankenstein::ParseMode;
+use frankenstein::PinChatMessageParams;
+use frankenstein::ReplyMarkup;
+use frankenstein::ResponseParameters;
 use frankenstein::SendChatActionParams;
 use frankenstein::SendMessageParams;
+use frankenstein::SetMyCommandsParams;
+use frankenstein::SetWebhookParams;
+use frankenstein::UnpinChatMessageParams;
 use frankenstein::Update;
 use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::sync::OnceCell;
 
-static API_CLIENT: OnceCell<ApiClient> = OnceCell::const_new();
+static API_CLIENTS: OnceCell<Vec<ApiClient>> = OnceCell::const_new();
+
+// A rate limited reply waits for the period Telegram asks and is sent again
+// this many times, as long as the period is not longer than MAX_REPLY_WAIT seconds.
+const REPLY_RETRIES: u32 = 3;
+const MAX_REPLY_WAIT: u16 = 30;
+
+/// Where a text split in several parts goes on: the index of its next part
+/// and the message that part replies to.
+#[derive(Serialize, Deserialize, Debug, Default, Eq, PartialEq, Clone, Copy)]
+#[serde(crate = "fang::serde")]
+pub struct TextProgress {
+    pub next_part: usize,
+    pub reply_to_message_id: Option<i32>,
+}
 
 #[derive(Debug, Error)]
 pub enum ApiError {
     #[error(transparent)]
     FrankensteinError(#[from] frankenstein::Error),
+    // The progress tells the parts of a split text that were already sent.
+    #[error("Too many requests, retry after {0} seconds")]
+    TooManyRequests(u16, TextProgress),
+    #[error("Chat can not receive messages: {0}")]
+    ChatUnreachable(String),
+    #[error("Message can not be edited: {0}")]
+    MessageUnavailable(String),
+    #[error("Bot {0} is not configured")]
+    UnknownBot(i64),
+    #[error("No part of the text is left to send")]
+    NothingToSend,
 }
 
 impl From<ApiError> for FangError {
@@ -33,32 +82,228 @@ impl From<ApiError> for FangError {
 
 #[derive(Clone)]
 pub struct ApiClient {
+    bot_id: i64,
+    bot_name: String,
     telegram_client: AsyncApi,
     update_params: GetUpdatesParams,
     buffer: VecDeque<Update>,
+    rate_limiter: Arc<RateLimiter>,
 }
 
 impl ApiClient {
-    pub async fn api_client() -> &'static Self {
-        API_CLIENT.get_or_init(ApiClient::new).await
+    pub async fn api_clients() -> Result<&'static [Self], ApiError> {
+        let clients = API_CLIENTS
+            .get_or_try_init(|| async {
+                let mut clients = Vec::new();
+
+                for token in BOT_TOKENS.iter() {
+                    clients.push(ApiClient::new(token).await?);
+                }
+
+                Ok::<_, ApiError>(clients)
+            })
+            .await?;
+
+        Ok(clients)
+    }
+
+    // Tasks queued before several bots could run carry no bot id,
+    // they belong to the primary bot.
+    pub async fn api_client(bot_id: i64) -> Result<&'static Self, ApiError> {
+        let clients = Self::api_clients().await?;
+
+        if bot_id == LEGACY_BOT_ID {
+            return Ok(&clients[0]);
+        }
+
+        clients
+            .iter()
+            .find(|client| client.bot_id == bot_id)
+            .ok_or(ApiError::UnknownBot(bot_id))
     }
 
-    pub async fn new() -> Self {
-        let telegram_client = AsyncApi::new(&RUST_TELEGRAM_BOT_TOKEN);
+    pub async fn new(token: &str) -> Result<Self, ApiError> {
+        let telegram_client = AsyncApi::new(token);
+
+        let me = telegram_client.get_me().await?.result;
+        let bot_id = me.id as i64;
+        let bot_name = me.username.unwrap_or_default();
 
         let update_params = GetUpdatesParams::builder()
-            .allowed_updates(vec![AllowedUpdate::Message, AllowedUpdate::ChannelPost])
+            .allowed_updates(Self::allowed_updates())
             .build();
 
         let buffer = VecDeque::new();
 
-        Self {
+        let rate_limiter = Arc::new(RateLimiter::new());
+
+        Ok(Self {
+            bot_id,
+            bot_name,
             telegram_client,
             update_params,
             buffer,
+            rate_limiter,
+        })
+    }
+
+    pub fn bot_id(&self) -> i64 {
+        self.bot_id
+    }
+
+    pub fn bot_name(&self) -> &str {
+        &self.bot_name
+    }
+
+    fn allowed_updates() -> Vec<AllowedUpdate> {
+        vec![
+            AllowedUpdate::Message,
+            AllowedUpdate::ChannelPost,
+            AllowedUpdate::CallbackQuery,
+            AllowedUpdate::InlineQuery,
+        ]
+    }
+
+    pub async fn set_webhook(
+        &self,
+        url: String,
+        secret_token: String,
+    ) -> Result<MethodResponse<bool>, ApiError> {
+        let set_webhook_params = SetWebhookParams::builder()
+            .url(url)
+            .secret_token(secret_token)
+            .allowed_updates(Self::allowed_updates())
+            .build();
+
+        Ok(self
+            .telegram_client
+            .set_webhook(&set_webhook_params)
+            .await?)
+    }
+
+    pub async fn delete_webhook(&self) -> Result<MethodResponse<bool>, ApiError> {
+        let delete_webhook_params = DeleteWebhookParams::builder().build();
+
+        Ok(self
+            .telegram_client
+            .delete_webhook(&delete_webhook_params)
+            .await?)
+    }
+
+    pub async fn set_my_commands(
+        &self,
+        commands: Vec<BotCommand>,
+        language_code: Option<String>,
+    ) -> Result<MethodResponse<bool>, ApiError> {
+        let mut set_my_commands_params = SetMyCommandsParams::builder().commands(commands).build();
+
+        set_my_commands_params.language_code = language_code;
+
+        Ok(self
+            .telegram_client
+            .set_my_commands(&set_my_commands_params)
+            .await?)
+    }
+
+    pub async fn get_chat_member(
+        &self,
+        chat_id: i64,
+        user_id: u64,
+    ) -> Result<MethodResponse<ChatMember>, ApiError> {
+        let get_chat_member_params = GetChatMemberParams::builder()
+            .chat_id(chat_id)
+            .user_id(user_id)
+            .build();
+
+        Ok(self
+            .telegram_client
+            .get_chat_member(&get_chat_member_params)
+            .await?)
+    }
+
+    pub async fn pin_chat_message(
+        &self,
+        chat_id: i64,
+        message_id: i32,
+    ) -> Result<MethodResponse<bool>, ApiError> {
+        let pin_chat_message_params = PinChatMessageParams::builder()
+            .chat_id(chat_id)
+            .message_id(message_id)
+            .disable_notification(true)
+            .build();
+
+        Ok(self
+            .telegram_client
+            .pin_chat_message(&pin_chat_message_params)
+            .await?)
+    }
+
+    pub async fn unpin_chat_message(
+        &self,
+        chat_id: i64,
+        message_id: i32,
+    ) -> Result<MethodResponse<bool>, ApiError> {
+        let unpin_chat_message_params = UnpinChatMessageParams::builder()
+            .chat_id(chat_id)
+            .message_id(message_id)
+            .build();
+
+        Ok(self
+            .telegram_client
+            .unpin_chat_message(&unpin_chat_message_params)
+            .await?)
+    }
+
+    // Telegram only tells the most recent pinned message of a chat.
+    pub async fn pinned_message_id(&self, chat_id: i64) -> Result<Option<i32>, ApiError> {
+        let get_chat_params = GetChatParams::builder().chat_id(chat_id).build();
+
+        let chat = self
+            .telegram_client
+            .get_chat(&get_chat_params)
+            .await
+            .map_err(|error| self.send_error(chat_id, error))?;
+
+        Ok(chat.result.pinned_message.map(|message| message.message_id))
+    }
+
+    // An edit that leaves the text as it was is not an error.
+    pub async fn edit_message_text(
+        &self,
+        chat_id: i64,
+        message_id: i32,
+        text: String,
+    ) -> Result<(), ApiError> {
+        let edit_message_text_params = EditMessageTextParams::builder()
+            .chat_id(chat_id)
+            .message_id(message_id)
+            .text(text)
+            .parse_mode(ParseMode::Html)
+            .build();
+
+        self.rate_limiter.wait(chat_id).await;
+
+        match self
+            .telegram_client
+            .edit_message_text(&edit_message_text_params)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(frankenstein::Error::Api(ErrorResponse { description, .. }))
+                if description.contains("message is not modified") =>
+            {
+                Ok(())
+            }
+            Err(error) => Err(self.send_error(chat_id, error)),
         }
     }
 
+    // Polling continues from `update_id`, the updates fetched after it are discarded.
+    pub fn rewind(&mut self, update_id: u32) {
+        self.buffer.clear();
+        self.update_params.offset = Some(update_id.into());
+    }
+
     pub async fn next_update(&mut self) -> Option<Update> {
         if let Some(update) = self.buffer.pop_front() {
             return Some(update);
@@ -102,33 +347,233 @@ impl ApiClient {
         message_id: i32,
         text: String,
     ) -> Result<MethodResponse<Message>, ApiError> {
-        let send_message_params = SendMessageParams::builder()
-            .chat_id(chat_id)
-            .text(text)
-            .reply_to_message_id(message_id)
-            .parse_mode(ParseMode::Html)
+        self.send_text(
+            chat_id,
+            text,
+            None,
+            Self::reply_to(message_id),
+            REPLY_RETRIES,
+        )
+        .await
+    }
+
+    pub async fn send_message_with_keyboard(
+        &self,
+        chat_id: i64,
+        message_id: i32,
+        text: String,
+        keyboard: InlineKeyboardMarkup,
+    ) -> Result<MethodResponse<Message>, ApiError> {
+        self.send_text(
+            chat_id,
+            text,
+            Some(keyboard),
+            Self::reply_to(message_id),
+            REPLY_RETRIES,
+        )
+        .await
+    }
+
+    pub async fn answer_callback_query(
+        &self,
+        callback_query_id: String,
+    ) -> Result<MethodResponse<bool>, ApiError> {
+        let answer_callback_query_params = AnswerCallbackQueryParams::builder()
+            .callback_query_id(callback_query_id)
             .build();
 
         Ok(self
             .telegram_client
-            .send_message(&send_message_params)
+            .answer_callback_query(&answer_callback_query_params)
             .await?)
     }
 
-    pub async fn send_message_without_reply(
+    pub async fn answer_inline_query(
         &self,
-        chat_id: i64,
-        text: String,
-    ) -> Result<MethodResponse<Message>, ApiError> {
-        let send_message_params = SendMessageParams::builder()
-            .chat_id(chat_id)
-            .text(text)
-            .parse_mode(ParseMode::Html)
+        inline_query_id: String,
+        results: Vec<InlineQueryResult>,
+        cache_time: u32,
+    ) -> Result<MethodResponse<bool>, ApiError> {
+        let answer_inline_query_params = AnswerInlineQueryParams::builder()
+            .inline_query_id(inline_query_id)
+            .results(results)
+            .cache_time(cache_time)
+            // Results are in the language and units of who asks,
+            // they can not be shown to other users.
+            .is_personal(true)
             .build();
 
         Ok(self
             .telegram_client
-            .send_message(&send_message_params)
+            .answer_inline_query(&answer_inline_query_params)
             .await?)
     }
+
+    pub async fn send_message_without_reply(
+        &self,
+        chat_id: i64,
+        text: String,
+    ) -> Result<MethodResponse<Message>, ApiError> {
+        self.send_message_from(chat_id, text, TextProgress::default())
+            .await
+    }
+
+    // Sends the parts of the text from `progress` on, without waiting when
+    // Telegram rate limits the chat. The error tells where to go on later.
+    pub async fn send_message_from(
+        &self,
+        chat_id: i64,
+        text: String,
+        progress: TextProgress,
+    ) -> Result<MethodResponse<Message>, ApiError> {
+        self.send_text(chat_id, text, None, progress, 0).await
+    }
+
+    fn reply_to(message_id: i32) -> TextProgress {
+        TextProgress {
+            next_part: 0,
+            reply_to_message_id: Some(message_id),
+        }
+    }
+
+    // Texts over the message limit are sent in several parts, the first one
+    // replies to `reply_to_message_id` and the rest reply to the first part.
+    // The keyboard goes with the last part. The response of the first sent part is returned.
+    async fn send_text(
+        &self,
+        chat_id: i64,
+        text: String,
+        keyboard: Option<InlineKeyboardMarkup>,
+        progress: TextProgress,
+        retries: u32,
+    ) -> Result<MethodResponse<Message>, ApiError> {
+        let parts = message_splitter::split_message(&text);
+
+        if progress.next_part >= parts.len() {
+            return Err(ApiError::NothingToSend);
+        }
+
+        let last_index = parts.len() - 1;
+
+        let mut progress = progress;
+        let mut first_response = None;
+
+        for (index, part) in parts.into_iter().enumerate().skip(progress.next_part) {
+            let mut send_message_params = SendMessageParams::builder()
+                .chat_id(chat_id)
+                .text(part)
+                .parse_mode(ParseMode::Html)
+                .build();
+
+            send_message_params.reply_to_message_id = progress.reply_to_message_id;
+
+            if index == last_index {
+                send_message_params.reply_markup =
+                    keyboard.clone().map(ReplyMarkup::InlineKeyboardMarkup);
+            }
+
+            let response = match self.send_part(chat_id, &send_message_params, retries).await {
+                Err(ApiError::TooManyRequests(retry_after, _)) => {
+                    return Err(ApiError::TooManyRequests(retry_after, progress))
+                }
+                result => result?,
+            };
+
+            if index == 0 {
+                progress.reply_to_message_id = Some(response.result.message_id);
+            }
+
+            progress.next_part = index + 1;
+
+            if first_response.is_none() {
+                first_response = Some(response);
+            }
+        }
+
+        first_response.ok_or(ApiError::NothingToSend)
+    }
+
+    // The rate limiter holds the next send to the chat for the period Telegram asked.
+    async fn send_part(
+        &self,
+        chat_id: i64,
+        send_message_params: &SendMessageParams,
+        retries: u32,
+    ) -> Result<MethodResponse<Message>, ApiError> {
+        let mut attempts = 0;
+
+        loop {
+            match self.send(chat_id, send_message_params).await {
+                Err(ApiError::TooManyRequests(retry_after, _))
+                    if attempts < retries && retry_after <= MAX_REPLY_WAIT =>
+                {
+                    log::info!(
+                        "Telegram asked to retry after {} seconds, waiting to reply to chat {}",
+                        retry_after,
+                        chat_id
+                    );
+
+                    attempts += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn send(
+        &self,
+        chat_id: i64,
+        send_message_params: &SendMessageParams,
+    ) -> Result<MethodResponse<Message>, ApiError> {
+        self.rate_limiter.wait(chat_id).await;
+
+        self.telegram_client
+            .send_message(send_message_params)
+            .await
+            .map_err(|error| self.send_error(chat_id, error))
+    }
+
+    fn send_error(&self, chat_id: i64, error: frankenstein::Error) -> ApiError {
+        match &error {
+            frankenstein::Error::Api(ErrorResponse {
+                parameters:
+                    Some(ResponseParameters {
+                        retry_after: Some(retry_after),
+                        ..
+                    }),
+                ..
+            }) => {
+                self.rate_limiter
+                    .pause(chat_id, Duration::from_secs((*retry_after).into()));
+
+                ApiError::TooManyRequests(*retry_after, TextProgress::default())
+            }
+
+            // The bot was blocked, kicked or the chat does not exist anymore.
+            // Retrying the message will never succeed.
+            frankenstein::Error::Api(ErrorResponse {
+                error_code,
+                description,
+                ..
+            }) if *error_code == 403
+                || (*error_code == 400 && description.contains("chat not found")) =>
+            {
+                ApiError::ChatUnreachable(description.clone())
+            }
+
+            // The message was deleted or is too old to be edited.
+            frankenstein::Error::Api(ErrorResponse {
+                error_code,
+                description,
+                ..
+            }) if *error_code == 400
+                && (description.contains("message to edit not found")
+                    || description.contains("message can't be edited")) =>
+            {
+                ApiError::MessageUnavailable(description.clone())
+            }
+
+            _ => ApiError::FrankensteinError(error),
+        }
+    }
 }
@@ -1,51 +1,75 @@
 use super::client::ApiClient;
 use crate::command::ProcessUpdateTask;
-use crate::DATABASE_URL;
-use fang::asynk::async_queue::AsyncQueue;
-use fang::asynk::async_queue::AsyncQueueable;
-use fang::NoTls;
+use crate::db::BotDbError;
+use crate::db::Repo;
+use crate::shutdown;
+use crate::BotError;
+use frankenstein::Update;
 use std::time::Duration;
 use tokio::time::sleep;
 
 pub struct Handler {
     client: ApiClient,
-    queue: AsyncQueue<NoTls>,
+    repo: &'static Repo,
 }
 
 impl Handler {
-    pub async fn new() -> Self {
-        let client = ApiClient::api_client().await.clone();
-        let queue = Self::init_queue().await;
+    pub async fn new(client: &ApiClient) -> Result<Self, BotError> {
+        let mut client = client.clone();
+        let repo = Repo::repo().await?;
 
-        Self { client, queue }
+        // Resume right after the last update enqueued before the restart.
+        if let Some(update_id) = repo.get_last_update_id(client.bot_id()).await? {
+            client.rewind(update_id + 1);
+        }
+
+        Ok(Self { client, repo })
     }
 
     pub async fn start(&mut self) {
-        loop {
+        // getUpdates is rejected by Telegram while a webhook is registered,
+        // so a previous webhook deployment has to be cleaned up first.
+        if let Err(err) = self.client.delete_webhook().await {
+            log::error!("Failed to delete webhook {:?}", err);
+        }
+
+        while !shutdown::is_requested() {
             while let Some(update) = self.client.next_update().await {
-                let task = ProcessUpdateTask::new(update);
-
-                if let Err(err) = self.queue.insert_task(&task).await {
-                    log::error!(
-                        "Failed to enqueue ProcessUpdateTask task {:?}, error {:?}",
-                        task,
-                        err
-                    );
+                // Updates not enqueued yet are fetched again after the restart.
+                if shutdown::is_requested() {
+                    break;
+                }
+
+                let update_id = update.update_id;
+
+                if let Err(err) =
+                    Self::enqueue_update(self.repo, self.client.bot_id(), update).await
+                {
+                    log::error!("Failed to enqueue ProcessUpdateTask task, error {:?}", err);
+
+                    // Telegram did not confirm it yet, so it is fetched again.
+                    self.client.rewind(update_id);
+                    break;
                 }
             }
 
-            sleep(Duration::from_secs(2)).await;
+            tokio::select! {
+                _ = sleep(Duration::from_secs(2)) => {}
+                _ = shutdown::requested() => {}
+            }
         }
-    }
 
-    async fn init_queue() -> AsyncQueue<NoTls> {
-        let mut queue: AsyncQueue<NoTls> = AsyncQueue::builder()
-            .uri(DATABASE_URL.clone())
-            .max_pool_size(1_u32)
-            .build();
+        log::info!("Polling of @{} stopped", self.client.bot_name());
+    }
 
-        queue.connect(NoTls).await.unwrap();
+    pub async fn enqueue_update(
+        repo: &Repo,
+        bot_id: i64,
+        update: Update,
+    ) -> Result<(), BotDbError> {
+        let update_id = update.update_id;
+        let task = ProcessUpdateTask::new(bot_id, update);
 
-        queue
+        repo.insert_update_task(bot_id, &task, update_id).await
     }
 }
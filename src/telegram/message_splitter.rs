@@ -0,0 +1,227 @@
+// Telegram rejects messages longer than 4096 characters (counted in UTF-16 code units).
+pub const MESSAGE_LIMIT: usize = 4096;
+
+// Every forecast entry starts with a `==== date ====` line.
+const ENTRY_HEADER: &str = "====";
+
+/// Splits `text` in parts that fit in one Telegram message.
+///
+/// Parts are cut before forecast entries or paragraphs when possible, then
+/// between lines and only as a last resort inside a line. HTML tags open at a
+/// cut are closed at the end of the part and opened again in the next one.
+pub fn split_message(text: &str) -> Vec<String> {
+    if length(text) <= MESSAGE_LIMIT {
+        return vec![text.to_string()];
+    }
+
+    let mut splitter = Splitter::default();
+
+    for block in blocks(text) {
+        splitter.push(block);
+    }
+
+    splitter.finish()
+}
+
+fn length(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+fn blocks(text: &str) -> Vec<&str> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    let mut position = 0;
+    let mut previous_empty = false;
+
+    for line in text.split_inclusive('\n') {
+        if position > start && (line.starts_with(ENTRY_HEADER) || previous_empty) {
+            blocks.push(&text[start..position]);
+            start = position;
+        }
+
+        previous_empty = line.trim().is_empty();
+        position += line.len();
+    }
+
+    blocks.push(&text[start..]);
+
+    blocks
+}
+
+// Smaller pieces of a unit that does not fit in a message: its lines,
+// or characters when it is a single line. Tags and entities are never cut.
+fn pieces(unit: &str) -> Vec<&str> {
+    let lines: Vec<&str> = unit.split_inclusive('\n').collect();
+
+    if lines.len() > 1 {
+        return lines;
+    }
+
+    let mut atoms = Vec::new();
+    let mut index = 0;
+
+    while index < unit.len() {
+        let character = unit[index..].chars().next().unwrap();
+
+        let end = match character {
+            '<' => unit[index..].find('>').map(|end| index + end + 1),
+            '&' => unit[index..]
+                .find(';')
+                .filter(|end| *end <= 10)
+                .map(|end| index + end + 1),
+            _ => None,
+        }
+        .unwrap_or(index + character.len_utf8());
+
+        atoms.push(&unit[index..end]);
+        index = end;
+    }
+
+    atoms
+}
+
+// Keeps the stack of open tags as (name, opening tag).
+fn apply_tags(open_tags: &mut Vec<(String, String)>, text: &str) {
+    let mut rest = text;
+
+    while let Some(start) = rest.find('<') {
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+
+        let tag = &rest[start + 1..end];
+
+        if let Some(name) = tag.strip_prefix('/') {
+            let name = name.trim();
+
+            if let Some(position) = open_tags.iter().rposition(|(open, _)| open == name) {
+                open_tags.truncate(position);
+            }
+        } else {
+            let name = tag.split_whitespace().next().unwrap_or_default();
+
+            open_tags.push((name.to_string(), rest[start..=end].to_string()));
+        }
+
+        rest = &rest[end + 1..];
+    }
+}
+
+fn closing_tags(open_tags: &[(String, String)]) -> String {
+    open_tags
+        .iter()
+        .rev()
+        .map(|(name, _)| format!("</{}>", name))
+        .collect()
+}
+
+#[derive(Default)]
+struct Splitter {
+    parts: Vec<String>,
+    current: String,
+    has_content: bool,
+    open_tags: Vec<(String, String)>,
+}
+
+impl Splitter {
+    fn push(&mut self, unit: &str) {
+        if self.fits(unit) {
+            return self.append(unit);
+        }
+
+        if self.has_content {
+            self.flush();
+
+            if self.fits(unit) {
+                return self.append(unit);
+            }
+        }
+
+        for piece in pieces(unit) {
+            self.push(piece);
+        }
+    }
+
+    fn fits(&self, unit: &str) -> bool {
+        let mut open_tags = self.open_tags.clone();
+        apply_tags(&mut open_tags, unit);
+
+        length(&self.current) + length(unit) + length(&closing_tags(&open_tags)) <= MESSAGE_LIMIT
+    }
+
+    fn append(&mut self, unit: &str) {
+        apply_tags(&mut self.open_tags, unit);
+
+        self.current.push_str(unit);
+        self.has_content = true;
+    }
+
+    fn flush(&mut self) {
+        let reopened: String = self.open_tags.iter().map(|(_, tag)| tag.as_str()).collect();
+
+        let mut part = std::mem::replace(&mut self.current, reopened);
+        part.push_str(&closing_tags(&self.open_tags));
+
+        if !part.trim().is_empty() {
+            self.parts.push(part);
+        }
+
+        self.has_content = false;
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        if self.has_content {
+            self.flush();
+        }
+
+        self.parts
+    }
+}
+
+#[cfg(test)]
+mod message_splitter_test {
+    use super::*;
+
+    fn entry(n: usize) -> String {
+        format!(
+            "\n==== entry {} ====\n🌍🌍 Weather: clear sky\n🌡️🌡️ Mean Temperature: 20 ºC",
+            n
+        )
+    }
+
+    #[test]
+    fn test_short_message_is_not_split() {
+        assert_eq!(split_message("Hi!"), vec!["Hi!".to_string()]);
+    }
+
+    #[test]
+    fn test_split_on_entry_boundaries() {
+        let text: String = (0..200).map(entry).collect();
+
+        let parts = split_message(&text);
+
+        assert!(parts.len() > 1);
+        assert_eq!(parts.concat(), text);
+
+        for part in parts.iter() {
+            assert!(length(part) <= MESSAGE_LIMIT);
+            assert!(part.trim_start().starts_with("==== entry"));
+        }
+    }
+
+    #[test]
+    fn test_split_keeps_tags_balanced() {
+        let text = format!("<b>{}</b>", "a".repeat(MESSAGE_LIMIT * 2));
+
+        let parts = split_message(&text);
+
+        assert_eq!(parts.len(), 3);
+
+        for part in parts.iter() {
+            assert!(length(part) <= MESSAGE_LIMIT);
+            assert!(part.starts_with("<b>"));
+            assert!(part.ends_with("</b>"));
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::time::sleep;
+
+// Telegram allows around 30 messages per second across all chats,
+// one message per second in a private chat and 20 messages per minute in a group.
+const GLOBAL_MESSAGES_PER_SECOND: usize = 30;
+const GLOBAL_WINDOW: Duration = Duration::from_secs(1);
+const PRIVATE_CHAT_INTERVAL: Duration = Duration::from_secs(1);
+const GROUP_CHAT_INTERVAL: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    state: Mutex<LimiterState>,
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    sent: VecDeque<Instant>,
+    next_send_per_chat: HashMap<i64, Instant>,
+}
+
+impl RateLimiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits until a message can be sent to `chat_id` without exceeding
+    /// the global or the per-chat budget and reserves that send.
+    pub async fn wait(&self, chat_id: i64) {
+        loop {
+            let delay = self.state.lock().unwrap().reserve(chat_id, Instant::now());
+
+            match delay {
+                None => return,
+                Some(delay) => sleep(delay).await,
+            }
+        }
+    }
+
+    /// Blocks sends to `chat_id` for the `retry_after` period returned by Telegram.
+    pub fn pause(&self, chat_id: i64, retry_after: Duration) {
+        let mut state = self.state.lock().unwrap();
+
+        state
+            .next_send_per_chat
+            .insert(chat_id, Instant::now() + retry_after);
+    }
+}
+
+impl LimiterState {
+    fn reserve(&mut self, chat_id: i64, now: Instant) -> Option<Duration> {
+        while let Some(sent_at) = self.sent.front() {
+            if now.duration_since(*sent_at) >= GLOBAL_WINDOW {
+                self.sent.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        if self.sent.len() >= GLOBAL_MESSAGES_PER_SECOND {
+            let oldest = self.sent.front().unwrap();
+
+            return Some(GLOBAL_WINDOW - now.duration_since(*oldest));
+        }
+
+        if let Some(next_send) = self.next_send_per_chat.get(&chat_id) {
+            if *next_send > now {
+                return Some(*next_send - now);
+            }
+        }
+
+        self.next_send_per_chat
+            .retain(|_, next_send| *next_send > now);
+
+        self.sent.push_back(now);
+        self.next_send_per_chat
+            .insert(chat_id, now + Self::chat_interval(chat_id));
+
+        None
+    }
+
+    // Group and channel ids are negative.
+    fn chat_interval(chat_id: i64) -> Duration {
+        if chat_id < 0 {
+            GROUP_CHAT_INTERVAL
+        } else {
+            PRIVATE_CHAT_INTERVAL
+        }
+    }
+}
+
+#[cfg(test)]
+mod rate_limiter_test {
+    use super::*;
+
+    #[test]
+    fn test_reserve_per_chat() {
+        let mut state = LimiterState::default();
+        let now = Instant::now();
+
+        assert_eq!(state.reserve(1, now), None);
+        assert_eq!(state.reserve(1, now), Some(PRIVATE_CHAT_INTERVAL));
+        assert_eq!(state.reserve(2, now), None);
+        assert_eq!(state.reserve(-2, now), None);
+        assert_eq!(
+            state.reserve(-2, now + PRIVATE_CHAT_INTERVAL),
+            Some(GROUP_CHAT_INTERVAL - PRIVATE_CHAT_INTERVAL)
+        );
+        assert_eq!(state.reserve(1, now + PRIVATE_CHAT_INTERVAL), None);
+    }
+
+    #[test]
+    fn test_reserve_global() {
+        let mut state = LimiterState::default();
+        let now = Instant::now();
+
+        for chat_id in 0..GLOBAL_MESSAGES_PER_SECOND as i64 {
+            assert_eq!(state.reserve(chat_id, now), None);
+        }
+
+        let later = now + Duration::from_millis(400);
+
+        assert_eq!(state.reserve(1000, later), Some(Duration::from_millis(600)));
+        assert_eq!(state.reserve(1000, now + GLOBAL_WINDOW), None);
+    }
+}
@@ -0,0 +1,374 @@
+use super::client::ApiClient;
+use super::handler::Handler;
+use crate::db::Repo;
+use crate::shutdown;
+use crate::BotError;
+use crate::WEBHOOK_ADDRESS;
+use crate::WEBHOOK_SECRET_TOKEN;
+use crate::WEBHOOK_URL;
+use frankenstein::Update;
+use hyper::body::HttpBody;
+use hyper::header::HeaderMap;
+use hyper::server::conn::AddrIncoming;
+use hyper::service::make_service_fn;
+use hyper::service::service_fn;
+use hyper::Body;
+use hyper::Method;
+use hyper::Request;
+use hyper::Response;
+use hyper::Server;
+use hyper::StatusCode;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+
+pub const SECRET_TOKEN_HEADER: &str = "X-Telegram-Bot-Api-Secret-Token";
+// Updates are a few kilobytes, a bigger body is not from Telegram.
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+pub struct WebhookServer {
+    clients: &'static [ApiClient],
+    repo: &'static Repo,
+    address: SocketAddr,
+    secret_token: String,
+}
+
+impl WebhookServer {
+    pub async fn new() -> Result<Self, BotError> {
+        let clients = ApiClient::api_clients().await?;
+        let repo = Repo::repo().await?;
+        let address = WEBHOOK_ADDRESS.parse::<SocketAddr>()?;
+        let secret_token = WEBHOOK_SECRET_TOKEN.clone();
+
+        Ok(Self {
+            clients,
+            repo,
+            address,
+            secret_token,
+        })
+    }
+
+    pub async fn start(&self) -> Result<(), BotError> {
+        // Every bot gets its own path so the server knows which bot an update is for.
+        for client in self.clients.iter() {
+            client
+                .set_webhook(
+                    Self::webhook_url(client.bot_id()),
+                    self.secret_token.clone(),
+                )
+                .await?;
+
+            log::info!("Webhook of @{} registered", client.bot_name());
+        }
+
+        let incoming = AddrIncoming::bind(&self.address)?;
+
+        log::info!("Listening on {}", incoming.local_addr());
+
+        let bot_ids: Vec<i64> = self.clients.iter().map(ApiClient::bot_id).collect();
+
+        Self::serve(incoming, self.repo, bot_ids, self.secret_token.clone()).await?;
+
+        log::info!("Webhook server stopped");
+
+        Ok(())
+    }
+
+    async fn serve(
+        incoming: AddrIncoming,
+        repo: &'static Repo,
+        bot_ids: Vec<i64>,
+        secret_token: String,
+    ) -> Result<(), hyper::Error> {
+        let make_service = make_service_fn(move |_connection| {
+            let secret_token = secret_token.clone();
+            let bot_ids = bot_ids.clone();
+
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    Self::handle(request, repo, bot_ids.clone(), secret_token.clone())
+                }))
+            }
+        });
+
+        // In-flight requests are answered before the server stops.
+        Server::builder(incoming)
+            .serve(make_service)
+            .with_graceful_shutdown(shutdown::requested())
+            .await
+    }
+
+    async fn handle(
+        request: Request<Body>,
+        repo: &'static Repo,
+        bot_ids: Vec<i64>,
+        secret_token: String,
+    ) -> Result<Response<Body>, Infallible> {
+        let (parts, body) = request.into_parts();
+
+        let bot_id = match Self::parse_bot_id(parts.uri.path(), &bot_ids) {
+            Ok(bot_id) => bot_id,
+            Err(status) => return Ok(Self::response(status)),
+        };
+
+        let body = match Self::read_body(body).await {
+            Ok(bytes) => bytes,
+            Err(status) => return Ok(Self::response(status)),
+        };
+
+        let update = match Self::parse_update(&parts.method, &parts.headers, &body, &secret_token) {
+            Ok(update) => update,
+            Err(status) => return Ok(Self::response(status)),
+        };
+
+        // Telegram keeps retrying an update until it gets a 2xx response,
+        // so a failed insert is reported back instead of swallowed.
+        match Handler::enqueue_update(repo, bot_id, update).await {
+            Ok(()) => Ok(Self::response(StatusCode::OK)),
+            Err(err) => {
+                log::error!("Failed to enqueue ProcessUpdateTask task, error {:?}", err);
+                Ok(Self::response(StatusCode::INTERNAL_SERVER_ERROR))
+            }
+        }
+    }
+
+    async fn read_body(mut body: Body) -> Result<Vec<u8>, StatusCode> {
+        let mut bytes = Vec::new();
+
+        while let Some(chunk) = body.data().await {
+            let chunk = chunk.map_err(|err| {
+                log::error!("Failed to read webhook request body {:?}", err);
+                StatusCode::BAD_REQUEST
+            })?;
+
+            if bytes.len() + chunk.len() > MAX_BODY_SIZE {
+                log::error!(
+                    "Webhook request body is bigger than {} bytes",
+                    MAX_BODY_SIZE
+                );
+                return Err(StatusCode::PAYLOAD_TOO_LARGE);
+            }
+
+            bytes.extend_from_slice(&chunk);
+        }
+
+        Ok(bytes)
+    }
+
+    pub fn webhook_url(bot_id: i64) -> String {
+        format!("{}/{}", WEBHOOK_URL.trim_end_matches('/'), bot_id)
+    }
+
+    fn parse_bot_id(path: &str, bot_ids: &[i64]) -> Result<i64, StatusCode> {
+        path.rsplit('/')
+            .next()
+            .and_then(|segment| segment.parse::<i64>().ok())
+            .filter(|bot_id| bot_ids.contains(bot_id))
+            .ok_or(StatusCode::NOT_FOUND)
+    }
+
+    fn parse_update(
+        method: &Method,
+        headers: &HeaderMap,
+        body: &[u8],
+        secret_token: &str,
+    ) -> Result<Update, StatusCode> {
+        if method != Method::POST {
+            return Err(StatusCode::METHOD_NOT_ALLOWED);
+        }
+
+        let header_token = headers
+            .get(SECRET_TOKEN_HEADER)
+            .and_then(|value| value.to_str().ok());
+
+        if header_token != Some(secret_token) {
+            log::error!("Webhook request with invalid secret token");
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+
+        serde_json::from_slice::<Update>(body).map_err(|err| {
+            log::error!("Failed to decode webhook update {:?}", err);
+            StatusCode::BAD_REQUEST
+        })
+    }
+
+    fn response(status: StatusCode) -> Response<Body> {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = status;
+
+        response
+    }
+}
+
+#[cfg(test)]
+mod webhook_test {
+    use super::*;
+    use hyper::header::HeaderValue;
+    use std::io::Read;
+    use std::io::Write;
+    use std::net::TcpStream;
+
+    const SECRET: &str = "secret";
+    const BOT_ID: i64 = 555;
+
+    const UPDATE: &str = r#"{
+        "update_id": 10000,
+        "message": {
+            "message_id": 1365,
+            "date": 1441645532,
+            "chat": {"id": 1111111, "type": "private", "first_name": "Test"},
+            "from": {"id": 1111111, "is_bot": false, "first_name": "Test"},
+            "text": "/start"
+        }
+    }"#;
+
+    // Sends the update like Telegram does and returns the status code of the answer.
+    fn post_update(address: SocketAddr, secret_token: Option<&str>) -> u16 {
+        let token_header = secret_token
+            .map(|token| format!("{}: {}\r\n", SECRET_TOKEN_HEADER, token))
+            .unwrap_or_default();
+
+        let request = format!(
+            "POST /weather/{} HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            BOT_ID,
+            token_header,
+            UPDATE.len(),
+            UPDATE
+        );
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        response
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .unwrap()
+    }
+
+    async fn enqueued_tasks(repo: &Repo) -> i64 {
+        repo.connection()
+            .await
+            .unwrap()
+            .query_one(
+                "SELECT COUNT(*) FROM fang_tasks WHERE metadata @> '{\"update\": {\"update_id\": 10000}}'",
+                &[],
+            )
+            .await
+            .unwrap()
+            .get(0)
+    }
+
+    fn headers(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(SECRET_TOKEN_HEADER, HeaderValue::from_str(token).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_parse_update() {
+        let update =
+            WebhookServer::parse_update(&Method::POST, &headers(SECRET), UPDATE.as_bytes(), SECRET)
+                .unwrap();
+
+        assert_eq!(update.update_id, 10000);
+    }
+
+    #[test]
+    fn test_parse_update_rejects_requests() {
+        let wrong_token = WebhookServer::parse_update(
+            &Method::POST,
+            &headers("other"),
+            UPDATE.as_bytes(),
+            SECRET,
+        );
+        assert_eq!(wrong_token.unwrap_err(), StatusCode::UNAUTHORIZED);
+
+        let no_token = WebhookServer::parse_update(
+            &Method::POST,
+            &HeaderMap::new(),
+            UPDATE.as_bytes(),
+            SECRET,
+        );
+        assert_eq!(no_token.unwrap_err(), StatusCode::UNAUTHORIZED);
+
+        let wrong_method =
+            WebhookServer::parse_update(&Method::GET, &headers(SECRET), UPDATE.as_bytes(), SECRET);
+        assert_eq!(wrong_method.unwrap_err(), StatusCode::METHOD_NOT_ALLOWED);
+
+        let bad_body = WebhookServer::parse_update(&Method::POST, &headers(SECRET), b"{}", SECRET);
+        assert_eq!(bad_body.unwrap_err(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_server_enqueues_updates() {
+        let repo = Repo::repo().await.unwrap();
+
+        let incoming = AddrIncoming::bind(&"127.0.0.1:0".parse().unwrap()).unwrap();
+        let address = incoming.local_addr();
+
+        let server = tokio::spawn(WebhookServer::serve(
+            incoming,
+            repo,
+            vec![BOT_ID],
+            SECRET.to_string(),
+        ));
+
+        let status = tokio::task::spawn_blocking(move || post_update(address, None))
+            .await
+            .unwrap();
+        assert_eq!(status, 401);
+        assert_eq!(enqueued_tasks(repo).await, 0);
+
+        let status = tokio::task::spawn_blocking(move || post_update(address, Some(SECRET)))
+            .await
+            .unwrap();
+        assert_eq!(status, 200);
+        assert_eq!(enqueued_tasks(repo).await, 1);
+
+        server.abort();
+
+        let connection = repo.connection().await.unwrap();
+
+        connection
+            .execute(
+                "DELETE FROM fang_tasks WHERE metadata @> '{\"update\": {\"update_id\": 10000}}'",
+                &[],
+            )
+            .await
+            .unwrap();
+        connection
+            .execute("DELETE FROM update_offsets WHERE bot_id = $1", &[&BOT_ID])
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_body_is_limited() {
+        let body = WebhookServer::read_body(Body::from(UPDATE)).await.unwrap();
+        assert_eq!(body, UPDATE.as_bytes());
+
+        let too_large = WebhookServer::read_body(Body::from(vec![0; MAX_BODY_SIZE + 1])).await;
+        assert_eq!(too_large.unwrap_err(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn test_parse_bot_id() {
+        let bot_ids = [111, 222];
+
+        assert_eq!(
+            WebhookServer::parse_bot_id("/weather/222", &bot_ids),
+            Ok(222)
+        );
+        assert_eq!(
+            WebhookServer::parse_bot_id("/weather/333", &bot_ids),
+            Err(StatusCode::NOT_FOUND)
+        );
+        assert_eq!(
+            WebhookServer::parse_bot_id("/weather", &bot_ids),
+            Err(StatusCode::NOT_FOUND)
+        );
+    }
+}
@@ -1,25 +1,76 @@
+use crate::command::process_update_task::partition_task_type;
+use crate::command::process_update_task::CleanProcessedUpdates;
+use crate::command::process_update_task::CHAT_PARTITIONS;
 use crate::command::process_update_task::TASK_TYPE;
+use crate::db::BotDbError;
+use crate::db::Repo;
 use crate::deliver::DeliverChecker;
+use crate::deliver::LiveMessageRefresher;
 use crate::deliver::SCHEDULED_TASK_TYPE;
+use crate::shutdown;
 use crate::DATABASE_URL;
+use crate::WORKER_ID;
 use fang::asynk::async_queue::AsyncQueue;
-use fang::asynk::async_worker_pool::AsyncWorkerPool;
 use fang::AsyncQueueable;
+use fang::AsyncRunnable;
 use fang::FangError;
 use fang::NoTls;
+use fang::Scheduled;
 use fang::SleepParams;
+use fang::Task;
 use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::timeout_at;
+use tokio::time::Instant;
 
 pub static NUMBER_OF_WORKERS: u32 = 5;
 
-pub async fn start_workers() -> Result<(), FangError> {
+/// Workers of the queue, they stop fetching tasks once the shutdown is requested.
+pub struct Workers {
+    queue: AsyncQueue<NoTls>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl Workers {
+    /// Waits until every worker finishes its current task or the timeout
+    /// expires, aborts the remaining ones and closes the pool of the queue.
+    /// Returns the number of aborted workers.
+    pub async fn stop(self, timeout: Duration) -> usize {
+        let deadline = Instant::now() + timeout;
+        let mut aborted = 0;
+
+        for mut handle in self.handles {
+            if timeout_at(deadline, &mut handle).await.is_err() {
+                handle.abort();
+                aborted += 1;
+            }
+        }
+
+        // The workers owned the other clones of the queue, dropping the last
+        // one drops its pool.
+        drop(self.queue);
+
+        aborted
+    }
+}
+
+pub async fn start_workers() -> Result<Workers, FangError> {
     let mut queue: AsyncQueue<NoTls> = AsyncQueue::builder()
         .uri(DATABASE_URL.clone())
-        .max_pool_size(NUMBER_OF_WORKERS)
+        .max_pool_size(NUMBER_OF_WORKERS + CHAT_PARTITIONS)
         .build();
 
     queue.connect(NoTls).await.unwrap();
 
+    let interrupted = Repo::repo()
+        .await?
+        .requeue_interrupted_tasks(&WORKER_ID)
+        .await?;
+
+    if interrupted > 0 {
+        log::info!("{} interrupted tasks queued again", interrupted);
+    }
+
     let params = SleepParams {
         sleep_period: Duration::from_millis(250),
         max_sleep_period: Duration::from_millis(1500),
@@ -27,23 +78,120 @@ pub async fn start_workers() -> Result<(), FangError> {
         sleep_step: Duration::from_millis(250),
     };
 
-    let mut pool: AsyncWorkerPool<AsyncQueue<NoTls>> = AsyncWorkerPool::builder()
-        .number_of_workers(NUMBER_OF_WORKERS)
-        .sleep_params(params.clone())
-        .queue(queue.clone())
-        .task_type(TASK_TYPE)
-        .build();
+    let mut handles = Vec::new();
 
-    let mut pool_scheduled: AsyncWorkerPool<AsyncQueue<NoTls>> = AsyncWorkerPool::builder()
-        .number_of_workers(NUMBER_OF_WORKERS)
-        .sleep_params(params)
-        .queue(queue.clone())
-        .task_type(SCHEDULED_TASK_TYPE)
-        .build();
+    for task_type in [TASK_TYPE, SCHEDULED_TASK_TYPE] {
+        for _ in 0..NUMBER_OF_WORKERS {
+            handles.push(spawn_worker(&queue, &params, task_type.to_string(), false));
+        }
+    }
 
-    pool.start().await;
-    pool_scheduled.start().await;
+    for partition in 0..CHAT_PARTITIONS {
+        handles.push(spawn_worker(
+            &queue,
+            &params,
+            partition_task_type(partition),
+            true,
+        ));
+    }
 
     queue.schedule_task(&DeliverChecker {}).await?;
+    queue.schedule_task(&LiveMessageRefresher {}).await?;
+    queue.schedule_task(&CleanProcessedUpdates {}).await?;
+
+    Ok(Workers { queue, handles })
+}
+
+// Restarts the worker if a task panics, like the worker pools of fang.
+fn spawn_worker(
+    queue: &AsyncQueue<NoTls>,
+    params: &SleepParams,
+    task_type: String,
+    ordered: bool,
+) -> JoinHandle<()> {
+    let queue = queue.clone();
+    let params = params.clone();
+
+    tokio::spawn(async move {
+        loop {
+            let worker = run_worker(queue.clone(), params.clone(), task_type.clone(), ordered);
+
+            match tokio::spawn(worker).await {
+                Ok(()) => return,
+                Err(err) => {
+                    log::error!("Worker of {} stopped, restarting {:?}", task_type, err)
+                }
+            }
+        }
+    })
+}
+
+async fn run_worker(
+    mut queue: AsyncQueue<NoTls>,
+    mut params: SleepParams,
+    task_type: String,
+    ordered: bool,
+) {
+    while !shutdown::is_requested() {
+        match fetch_task(&task_type, ordered).await {
+            Ok(Some(task)) => {
+                params.maybe_reset_sleep_period();
+
+                if let Err(err) = run_task(&mut queue, task).await {
+                    log::error!("Failed to finalize a task {:?}", err);
+                }
+            }
+            Ok(None) => sleep(&mut params).await,
+            Err(err) => {
+                log::error!("Failed to fetch a task {:?}", err);
+
+                sleep(&mut params).await;
+            }
+        }
+    }
+}
+
+async fn fetch_task(task_type: &str, ordered: bool) -> Result<Option<Task>, BotDbError> {
+    Repo::repo()
+        .await?
+        .fetch_task(task_type, ordered, &WORKER_ID)
+        .await
+}
+
+async fn sleep(params: &mut SleepParams) {
+    params.maybe_increase_sleep_period();
+
+    tokio::select! {
+        _ = tokio::time::sleep(params.sleep_period) => {}
+        _ = shutdown::requested() => {}
+    }
+}
+
+// Runs the task and then removes it or schedules its retry, as fang does
+// with its default retention mode.
+async fn run_task(queue: &mut AsyncQueue<NoTls>, task: Task) -> Result<(), FangError> {
+    let runnable: Box<dyn AsyncRunnable> = serde_json::from_value(task.metadata.clone())?;
+
+    if let Some(Scheduled::CronPattern(_)) = runnable.cron() {
+        queue.schedule_task(&*runnable).await?;
+    }
+
+    match runnable.run(queue).await {
+        // The task did not start, it is not a failed attempt.
+        Err(err) if shutdown::is_shutdown_error(&err) => {
+            Repo::repo().await?.put_back_task(&task).await?;
+        }
+        Err(err) if task.retries < runnable.max_retries() => {
+            let backoff_seconds = runnable.backoff(task.retries as u32);
+
+            queue
+                .schedule_retry(&task, backoff_seconds, &err.description)
+                .await?;
+        }
+        _ => {
+            queue.remove_task(task.id).await?;
+        }
+    }
+
     Ok(())
 }